@@ -0,0 +1,129 @@
+// Rendering of RGB images with block characters and truecolor SGR
+// Fallback for terminals without any graphics protocol.
+
+use crate::csi::{self, SgrCode, SgrColor};
+use std::io;
+
+type Rgb = (u8, u8, u8);
+
+// indexed by the mask of foreground pixels: top left 1, top right 2, bottom left 4, bottom right 8
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+/// Draw pixels, rows of width pixels, with one upper half block per 1x2 pixels.
+/// Rows are separated by CR LF and the cursor is left at the end of the last row.
+/// The last row is repeated if the height is odd.
+pub fn half_blocks<W: io::Write>(w: &mut W, pixels: &[Rgb], width: usize) -> io::Result<()> {
+    render(w, pixels, width, 1, |cell| ('▀', cell[0], cell[2]))
+}
+
+/// Draw pixels, rows of width pixels, with one quadrant character per 2x2 pixels.
+/// Each cell gets the two colors that approximate its four pixels best.
+/// Rows are separated by CR LF and edge pixels are repeated to fill the last cells.
+pub fn quadrants<W: io::Write>(w: &mut W, pixels: &[Rgb], width: usize) -> io::Result<()> {
+    render(w, pixels, width, 2, quadrant)
+}
+
+// cell pixels are given in the order of top left, top right, bottom left and bottom right
+fn render<W, F>(w: &mut W, pixels: &[Rgb], width: usize, cell_width: usize, f: F) -> io::Result<()>
+where
+    W: io::Write,
+    F: Fn(&[Rgb; 4]) -> (char, Rgb, Rgb),
+{
+    if width == 0 || pixels.is_empty() {
+        return Ok(());
+    }
+    let height = pixels.len() / width;
+    let px = |x: usize, y: usize| pixels[y.min(height - 1) * width + x.min(width - 1)];
+    for y in (0..height).step_by(2) {
+        if y > 0 {
+            w.write_all(b"\r\n")?;
+        }
+        let mut prev = None;
+        for x in (0..width).step_by(cell_width) {
+            let dx = cell_width - 1;
+            let cell = [px(x, y), px(x + dx, y), px(x, y + 1), px(x + dx, y + 1)];
+            let (c, fg, bg) = f(&cell);
+            if prev != Some((fg, bg)) {
+                csi::sgr_color(w, SgrColor::FgColor24bit(fg))?;
+                csi::sgr_color(w, SgrColor::BgColor24bit(bg))?;
+                prev = Some((fg, bg));
+            }
+            write!(w, "{}", c)?;
+        }
+        csi::sgr(w, SgrCode::Normal)?;
+    }
+    Ok(())
+}
+
+fn quadrant(cell: &[Rgb; 4]) -> (char, Rgb, Rgb) {
+    // a full block wins ties, so uniform cells are drawn with one color
+    let all = mean(cell, |_| true);
+    let err: u32 = cell.iter().map(|&c| distance(c, all)).sum();
+    let mut best = (err, 15, all, all);
+    for mask in 1..15 {
+        let fg = mean(cell, |i| mask & (1 << i) != 0);
+        let bg = mean(cell, |i| mask & (1 << i) == 0);
+        let err: u32 = (0..4)
+            .map(|i| distance(cell[i], if mask & (1 << i) != 0 { fg } else { bg }))
+            .sum();
+        if err < best.0 {
+            best = (err, mask, fg, bg);
+        }
+    }
+    (QUADRANTS[best.1], best.2, best.3)
+}
+
+// pick must select at least one pixel
+fn mean<F: Fn(usize) -> bool>(cell: &[Rgb; 4], pick: F) -> Rgb {
+    let picked: Vec<Rgb> = (0..4).filter(|&i| pick(i)).map(|i| cell[i]).collect();
+    let n = picked.len() as u32;
+    let sum = picked.iter().fold((0, 0, 0), |s, c| {
+        (
+            s.0 + u32::from(c.0),
+            s.1 + u32::from(c.1),
+            s.2 + u32::from(c.2),
+        )
+    });
+    ((sum.0 / n) as u8, (sum.1 / n) as u8, (sum.2 / n) as u8)
+}
+
+fn distance(a: Rgb, b: Rgb) -> u32 {
+    let d = |x: u8, y: u8| (i32::from(x) - i32::from(y)).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const R: Rgb = (255, 0, 0);
+    const B: Rgb = (0, 0, 255);
+
+    #[test]
+    fn test_half_blocks() {
+        let mut w = Vec::new();
+        half_blocks(&mut w, &[R, B, B, B, R, R], 2).unwrap();
+        let s = String::from_utf8(w).unwrap();
+        assert_eq!(
+            s,
+            "\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m▀\x1b[38;2;0;0;255m\x1b[48;2;0;0;255m▀\x1b[0m\r\n\
+             \x1b[38;2;255;0;0m\x1b[48;2;255;0;0m▀▀\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_quadrant() {
+        assert_eq!(quadrant(&[R, B, R, B]), ('▌', R, B));
+        assert_eq!(quadrant(&[R, R, R, B]), ('▛', R, B));
+        assert_eq!(quadrant(&[B, R, R, B]), ('▞', R, B));
+        assert_eq!(quadrant(&[R, R, R, R]).0, '█');
+        let mut w = Vec::new();
+        quadrants(&mut w, &[R, B, R, B], 2).unwrap();
+        assert_eq!(
+            String::from_utf8(w).unwrap(),
+            "\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m▌\x1b[0m"
+        );
+    }
+}
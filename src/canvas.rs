@@ -0,0 +1,143 @@
+// Plotting on braille characters, 2x4 dots per cell
+
+use crate::csi::{self, SgrCode, SgrColor};
+use std::io;
+
+// dot bits of U+2800.. indexed by [y][x] within a cell
+const DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Dot grid drawn with braille characters.
+/// Dot coordinates are zero based from the top left, dots out of the canvas are ignored.
+#[derive(Debug, Clone)]
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+    colors: Vec<Option<u8>>,
+}
+
+impl Canvas {
+    /// Canvas of width x height cells, that is width * 2 x height * 4 dots.
+    pub fn new(width: usize, height: usize) -> Canvas {
+        Canvas {
+            width,
+            height,
+            cells: vec![0; width * height],
+            colors: vec![None; width * height],
+        }
+    }
+
+    /// Size in dots, (width, height).
+    pub fn dots(&self) -> (usize, usize) {
+        (self.width * 2, self.height * 4)
+    }
+
+    pub fn set(&mut self, x: usize, y: usize) {
+        if let Some((i, bit)) = self.index(x, y) {
+            self.cells[i] |= bit;
+        }
+    }
+
+    /// Set a dot and color its cell with an 8 bit color.
+    /// A cell has one color, so the last one set wins.
+    pub fn set_color(&mut self, x: usize, y: usize, color: u8) {
+        if let Some((i, bit)) = self.index(x, y) {
+            self.cells[i] |= bit;
+            self.colors[i] = Some(color);
+        }
+    }
+
+    pub fn unset(&mut self, x: usize, y: usize) {
+        if let Some((i, bit)) = self.index(x, y) {
+            self.cells[i] &= !bit;
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.index(x, y)
+            .is_some_and(|(i, bit)| self.cells[i] & bit != 0)
+    }
+
+    /// Set dots on the line from (x0, y0) to (x1, y1).
+    pub fn line(&mut self, x0: usize, y0: usize, x1: usize, y1: usize) {
+        let (x0, y0, x1, y1) = (x0 as isize, y0 as isize, x1 as isize, y1 as isize);
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+        loop {
+            self.set(x as usize, y as usize);
+            if x == x1 && y == y1 {
+                return;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|c| *c = 0);
+        self.colors.iter_mut().for_each(|c| *c = None);
+    }
+
+    /// Draw the canvas at the cursor.
+    /// Rows are separated by CR LF and the cursor is left at the end of the last row.
+    pub fn draw<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        for row in 0..self.height {
+            if row > 0 {
+                w.write_all(b"\r\n")?;
+            }
+            let mut color = None;
+            for i in row * self.width..(row + 1) * self.width {
+                if self.colors[i] != color {
+                    match self.colors[i] {
+                        Some(c) => csi::sgr_color(w, SgrColor::FgColor8bit(c))?,
+                        None => csi::sgr(w, SgrCode::FgColorDefault)?,
+                    }
+                    color = self.colors[i];
+                }
+                let c = std::char::from_u32(0x2800 + u32::from(self.cells[i])).unwrap_or(' ');
+                write!(w, "{}", c)?;
+            }
+            if color.is_some() {
+                csi::sgr(w, SgrCode::Normal)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<(usize, u8)> {
+        if x >= self.width * 2 || y >= self.height * 4 {
+            return None;
+        }
+        Some(((y / 4) * self.width + x / 2, DOTS[y % 4][x % 2]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canvas() {
+        let mut c = Canvas::new(2, 1);
+        assert_eq!(c.dots(), (4, 4));
+        c.line(0, 0, 3, 3);
+        c.set(9, 9);
+        assert!(c.get(1, 1) && !c.get(1, 0));
+        let mut w = Vec::new();
+        c.draw(&mut w).unwrap();
+        assert_eq!(String::from_utf8(w).unwrap(), "⠑⢄");
+        c.unset(0, 0);
+        c.set_color(3, 0, 1);
+        let mut w = Vec::new();
+        c.draw(&mut w).unwrap();
+        assert_eq!(String::from_utf8(w).unwrap(), "⠐\x1b[38;5;1m⢌\x1b[0m");
+    }
+}
@@ -2,6 +2,7 @@ use termios;
 
 // CSI(Control Sequence Introducer) of Escapse sequence
 
+use crate::esc::{decrc, decsc};
 use crate::{echo_off, echo_on};
 use std::io::{self, Read};
 
@@ -25,6 +26,11 @@ pub enum ElClear {
     EntireLine = 2,
 }
 
+pub enum TbcClear {
+    CurrentColumn = 0,
+    AllColumns = 3,
+}
+
 pub enum SgrCode {
     Normal = 0,
     Bold = 1,
@@ -112,6 +118,147 @@ pub enum SgrColor {
     BgColor24bit((u8, u8, u8)),
 }
 
+/// Item of XTSMGRAPHICS.
+pub enum GraphicsItem {
+    ColorRegisters = 1,
+    SixelGeometry = 2,
+    RegisGeometry = 3,
+}
+
+/// Action of XTSMGRAPHICS.
+pub enum GraphicsAction {
+    Read = 1,
+    Reset = 2,
+    ReadMax = 4,
+}
+
+/// Graphics limits reported by XTSMGRAPHICS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphicsLimits {
+    pub color_registers: usize,
+    pub sixel_width: usize,
+    pub sixel_height: usize,
+}
+
+/// Primary device attributes reported by DA1.
+/// Feature codes are meaningful for VT220 and later classes (62 and above).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceAttributes {
+    /// Conformance class, e.g. 62 for VT220, 64 for VT420
+    pub class: usize,
+    pub features: Vec<usize>,
+}
+
+impl DeviceAttributes {
+    pub fn has(&self, feature: usize) -> bool {
+        self.features.contains(&feature)
+    }
+
+    pub fn columns_132(&self) -> bool {
+        self.has(1)
+    }
+
+    pub fn printer(&self) -> bool {
+        self.has(2)
+    }
+
+    pub fn regis(&self) -> bool {
+        self.has(3)
+    }
+
+    pub fn sixel(&self) -> bool {
+        self.has(4)
+    }
+
+    pub fn selective_erase(&self) -> bool {
+        self.has(6)
+    }
+
+    pub fn user_defined_keys(&self) -> bool {
+        self.has(8)
+    }
+
+    pub fn ansi_color(&self) -> bool {
+        self.has(22)
+    }
+
+    pub fn rectangular_editing(&self) -> bool {
+        self.has(28)
+    }
+
+    pub fn locator(&self) -> bool {
+        self.has(29)
+    }
+}
+
+pub enum DecssdtType {
+    None = 0,
+    Indicator = 1,
+    HostWritable = 2,
+}
+
+pub enum DecsasdDisplay {
+    Main = 0,
+    StatusLine = 1,
+}
+
+/// Where status_line writes.
+pub enum StatusLine {
+    /// Host-writable DEC status display
+    Dec,
+    /// The last line of a screen with this many rows, kept out of the scrolling region
+    Pinned(usize),
+}
+
+/// Secondary device attributes reported by DA2.
+/// terminal identifies the emulator, e.g. 0 for VT100, 41 for xterm (VT420), 84 for tmux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalVersion {
+    pub terminal: usize,
+    /// Firmware version, xterm reports its patch number and VTE its version like 6800 for 0.68.0
+    pub version: usize,
+    pub rom: usize,
+}
+
+/// DEC private modes of the alternate screen.
+#[derive(Clone, Copy)]
+pub enum AltScreen {
+    /// 47: switch screens only
+    Switch = 47,
+    /// 1047: switch screens, clearing the alternate screen when leaving it
+    SwitchClear = 1047,
+    /// 1048: save and restore the cursor only, without switching screens
+    SaveCursor = 1048,
+    /// 1049: save the cursor and switch to the cleared alternate screen
+    SwitchSaveCursor = 1049,
+}
+
+/// Mouse events reported to the application.
+#[derive(Clone, Copy)]
+pub enum MouseMode {
+    /// 1000: button press and release
+    Click = 1000,
+    /// 1002: also motion while a button is held
+    Drag = 1002,
+    /// 1003: also motion without buttons
+    AnyMotion = 1003,
+}
+
+/// Encoding of mouse reports.
+#[derive(Clone, Copy)]
+pub enum MouseEncoding {
+    /// 1006: `CSI < b;x;y M/m` with cell coordinates of any size
+    Sgr = 1006,
+    /// 1016: same as Sgr with pixel coordinates
+    SgrPixels = 1016,
+}
+
+/// How the cursor is saved and restored.
+pub enum CursorSave {
+    Scp,   // SCP/RCP
+    Decsc, // DECSC/DECRC, also saves SGR attributes
+}
+
 pub enum DecscusrStyle {
     BlinkingBlock = 1,
     SteadyBlock = 2,
@@ -121,6 +268,21 @@ pub enum DecscusrStyle {
     SteadyBar = 6,
 }
 
+pub enum DecelrMode {
+    Disable = 0,
+    Enable = 1,
+    OneShot = 2,
+}
+
+#[derive(Clone, Copy)]
+pub enum DecsleEvent {
+    RequestOnly = 0,
+    ButtonDown = 1,
+    NoButtonDown = 2,
+    ButtonUp = 3,
+    NoButtonUp = 4,
+}
+
 // avoid zero
 fn _nz(n: usize) -> usize {
     if n == 0 {
@@ -130,6 +292,240 @@ fn _nz(n: usize) -> usize {
     }
 }
 
+/// Iterate parameters of a control sequence without allocation.
+/// buf is the parameter bytes between CSI and the final byte, e.g. b"1;38:2::10:20:30".
+/// No parameter is yielded for empty buf.
+pub fn params(buf: &[u8]) -> Params<'_> {
+    Params {
+        buf,
+        done: buf.is_empty(),
+    }
+}
+
+/// Iterator over ';' separated parameters.
+#[derive(Debug, Clone)]
+pub struct Params<'a> {
+    buf: &'a [u8],
+    done: bool,
+}
+
+impl<'a> Iterator for Params<'a> {
+    type Item = Param<'a>;
+
+    fn next(&mut self) -> Option<Param<'a>> {
+        if self.done {
+            return None;
+        }
+        match self.buf.iter().position(|&b| b == b';') {
+            Some(i) => {
+                let p = Param(&self.buf[..i]);
+                self.buf = &self.buf[i + 1..];
+                Some(p)
+            }
+            None => {
+                self.done = true;
+                Some(Param(self.buf))
+            }
+        }
+    }
+}
+
+/// A parameter with ':' separated sub-parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Param<'a>(&'a [u8]);
+
+impl<'a> Param<'a> {
+    /// Value before the first ':'. None if it's omitted (default) or not a number.
+    pub fn value(&self) -> Option<usize> {
+        self.subparams().next().flatten()
+    }
+
+    pub fn value_or(&self, default: usize) -> usize {
+        self.value().unwrap_or(default)
+    }
+
+    /// All values including the first one. Omitted values are None.
+    pub fn subparams(&self) -> impl Iterator<Item = Option<usize>> + 'a {
+        self.0.split(|&b| b == b':').map(|s| {
+            if s.is_empty() || !s.iter().all(u8::is_ascii_digit) {
+                return None;
+            }
+            Some(s.iter().fold(0usize, |n, &b| {
+                n.saturating_mul(10).saturating_add(usize::from(b - b'0'))
+            }))
+        })
+    }
+}
+
+// send a query with echo off and read its reply by f
+pub(crate) fn query<W, R, F, T>(w: &mut W, r: &mut R, f: F) -> Option<T>
+where
+    W: io::Write,
+    F: FnOnce(&mut W, &mut R) -> Option<T>,
+{
+    let oldstat = echo_off();
+    let ret = f(w, r);
+    echo_on(&oldstat);
+    ret
+}
+
+// numeric parameters of a CSI reply read by read_sequence, between prefix and the final byte
+// omitted parameters are 0
+// => "${prefix}${p1};${p2}...${final}"
+pub(crate) fn reply_params(seq: &[u8], prefix: &[u8], final_byte: u8) -> Option<Vec<usize>> {
+    let body = seq.strip_prefix(prefix)?.strip_suffix(&[final_byte])?;
+    Some(params(body).map(|p| p.value_or(0)).collect())
+}
+
+/// Default of `ReplyReader::max_len`.
+pub const DEFAULT_REPLY_MAX_LEN: usize = 4096;
+
+/// Lower bound of `ReplyReader::max_len`, longer than any DA1 reply, which ends most queries.
+pub const MIN_REPLY_MAX_LEN: usize = 256;
+
+/// An escape sequence read from the terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    Sequence(Vec<u8>),
+    /// A sequence longer than the limit, read to its end. Only its head up to the limit is kept.
+    Truncated(Vec<u8>),
+}
+
+/// Source of replies for queries.
+/// A plain reader has the default limit. Wrap it in `ReplyReader` to change the limit
+/// or to learn whether a reply was truncated.
+pub trait ReadReply {
+    /// Read one whole escape sequence, bytes before ESC are skipped.
+    /// None is returned only when the reader fails or ends.
+    fn read_reply(&mut self) -> Option<Reply>;
+}
+
+impl<R: io::Read> ReadReply for R {
+    fn read_reply(&mut self) -> Option<Reply> {
+        read_sequence(self, DEFAULT_REPLY_MAX_LEN)
+    }
+}
+
+/// Reader of replies with a limit on their length,
+/// so a broken or hostile reply can't make a query allocate unbounded memory.
+pub struct ReplyReader<R: io::Read> {
+    inner: R,
+    max_len: usize,
+    truncated: usize,
+}
+
+impl<R: io::Read> ReplyReader<R> {
+    pub fn new(inner: R) -> ReplyReader<R> {
+        ReplyReader {
+            inner,
+            max_len: DEFAULT_REPLY_MAX_LEN,
+            truncated: 0,
+        }
+    }
+
+    /// Set the maximum length of a reply, e.g. higher for long titles, lower on constrained systems.
+    /// Values below MIN_REPLY_MAX_LEN are raised to it.
+    pub fn max_len(mut self, n: usize) -> ReplyReader<R> {
+        self.max_len = n.max(MIN_REPLY_MAX_LEN);
+        self
+    }
+
+    /// Number of truncated replies read so far.
+    /// Queries skip them, so this tells a reply too long from an unsupported query.
+    pub fn truncated(&self) -> usize {
+        self.truncated
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: io::Read> ReadReply for ReplyReader<R> {
+    fn read_reply(&mut self) -> Option<Reply> {
+        let reply = read_sequence(&mut self.inner, self.max_len)?;
+        if let Reply::Truncated(_) = reply {
+            self.truncated += 1;
+        }
+        Some(reply)
+    }
+}
+
+// CSI ends with a final byte, OSC/DCS/APC/PM end with BEL or ST
+fn read_sequence<R: io::Read>(r: &mut R, max_len: usize) -> Option<Reply> {
+    let mut b = [0u8];
+    loop {
+        r.read_exact(&mut b).ok()?;
+        if b[0] == 0x1b {
+            break;
+        }
+    }
+    let mut seq = vec![0x1b];
+    r.read_exact(&mut b).ok()?;
+    seq.push(b[0]);
+    let string = match b[0] {
+        b'[' => false,
+        b']' | b'P' | b'_' | b'^' => true,
+        _ => return Some(Reply::Sequence(seq)),
+    };
+    let (mut prev, mut overflow) = (0u8, false);
+    loop {
+        r.read_exact(&mut b).ok()?;
+        if seq.len() < max_len {
+            seq.push(b[0]);
+        } else {
+            overflow = true;
+        }
+        let end = if string {
+            b[0] == 0x07 || (prev == 0x1b && b[0] == b'\\')
+        } else {
+            (0x40..=0x7e).contains(&b[0])
+        };
+        if end {
+            return Some(if overflow {
+                Reply::Truncated(seq)
+            } else {
+                Reply::Sequence(seq)
+            });
+        }
+        prev = b[0];
+    }
+}
+
+// read replies to a query followed by DA1 and pass them to f until DA1 arrives
+// truncated string replies are skipped, and as no query is answered by a CSI that long,
+// a truncated CSI is taken as a broken DA1 rather than waiting for another one
+// None is returned only when r fails or ends
+pub(crate) fn read_until_da1<R: ReadReply, F: FnMut(&[u8])>(r: &mut R, mut f: F) -> Option<()> {
+    loop {
+        match r.read_reply()? {
+            Reply::Sequence(seq) => {
+                if reply_params(&seq, b"\x1b[?", b'c').is_some() {
+                    return Some(());
+                }
+                f(&seq);
+            }
+            Reply::Truncated(head) if head.starts_with(b"\x1b[") => return Some(()),
+            Reply::Truncated(_) => {}
+        }
+    }
+}
+
+// payload of a string sequence which starts with prefix
+pub(crate) fn string_payload<'a>(seq: &'a [u8], prefix: &[u8]) -> Option<&'a [u8]> {
+    let body = seq.strip_prefix(prefix)?;
+    body.strip_suffix(b"\x07")
+        .or_else(|| body.strip_suffix(b"\x1b\\"))
+}
+
 /// CUU: cursor up
 pub fn cuu<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
     w.write_fmt(format_args!(csi!("{}A"), _nz(n)))?;
@@ -173,12 +569,38 @@ pub fn cha<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
     Ok(())
 }
 
+/// HPR: horizontal position relative
+pub fn hpr<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("{}a"), _nz(n)))?;
+    Ok(())
+}
+
+/// VPA: vertical position absolute
+/// Move to row keeping the column.
+pub fn vpa<W: io::Write>(w: &mut W, row: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("{}d"), _nz(row)))?;
+    Ok(())
+}
+
+/// VPR: vertical position relative
+pub fn vpr<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("{}e"), _nz(n)))?;
+    Ok(())
+}
+
 /// CUP: cursor position
 pub fn cup<W: io::Write>(w: &mut W, row: usize, col: usize) -> io::Result<()> {
     w.write_fmt(format_args!(csi!("{};{}H"), _nz(row), _nz(col)))?;
     Ok(())
 }
 
+/// HVP: horizontal and vertical position
+/// Same as CUP, for terminals which honor only HVP.
+pub fn hvp<W: io::Write>(w: &mut W, row: usize, col: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("{};{}f"), _nz(row), _nz(col)))?;
+    Ok(())
+}
+
 /// ED: erase in display
 /// If n is 0 (or missing), clear from cursor to end of screen.
 /// If n is 1, clear from cursor to beginning of the screen.
@@ -204,6 +626,12 @@ pub fn el<W: io::Write>(w: &mut W, n: ElClear) -> io::Result<()> {
     Ok(())
 }
 
+/// TBC: tab clear
+pub fn tbc<W: io::Write>(w: &mut W, n: TbcClear) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("{}g"), n as usize))?;
+    Ok(())
+}
+
 /// SU: scroll up
 pub fn su<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
     w.write_fmt(format_args!(csi!("{}S"), _nz(n)))?;
@@ -216,6 +644,30 @@ pub fn sd<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
     Ok(())
 }
 
+/// IL: insert lines
+pub fn il<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("{}L"), _nz(n)))?;
+    Ok(())
+}
+
+/// DL: delete lines
+pub fn dl<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("{}M"), _nz(n)))?;
+    Ok(())
+}
+
+/// ECH: erase characters
+pub fn ech<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("{}X"), _nz(n)))?;
+    Ok(())
+}
+
+/// REP: repeat the preceding graphic character
+pub fn rep<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("{}b"), _nz(n)))?;
+    Ok(())
+}
+
 /// SGR: select graphic rendition
 /// SGR parameters: https://en.wikipedia.org/wiki/ANSI_escape_code#SGR_(Select_Graphic_Rendition)_parameters
 pub fn sgr<W: io::Write>(w: &mut W, c: SgrCode) -> io::Result<()> {
@@ -266,6 +718,90 @@ pub fn dsr<W: io::Write, R: io::Read>(w: &mut W, r: &mut R) -> Option<(usize, us
     Some((row, col))
 }
 
+/// DA1: primary device attributes
+pub fn da1<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<DeviceAttributes> {
+    query(w, r, da1_reply)
+}
+
+fn da1_reply<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<DeviceAttributes> {
+    w.write_fmt(format_args!(csi!("c"))).ok()?;
+    w.flush().ok()?;
+    // => "\x1b[?${class};${feature}...c"
+    loop {
+        let seq = match r.read_reply()? {
+            Reply::Sequence(seq) => seq,
+            // no DA1 reply is that long, so it won't come
+            Reply::Truncated(head) if head.starts_with(b"\x1b[") => return None,
+            Reply::Truncated(_) => continue,
+        };
+        if let Some(params) = reply_params(&seq, b"\x1b[?", b'c') {
+            let (&class, features) = params.split_first()?;
+            return Some(DeviceAttributes {
+                class,
+                features: features.to_vec(),
+            });
+        }
+    }
+}
+
+/// DA2: secondary device attributes
+/// DA1 is sent after the query, so None is returned instead of blocking
+/// when the terminal doesn't answer DA2.
+pub fn da2<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<TerminalVersion> {
+    query(w, r, da2_reply)
+}
+
+pub(crate) fn da2_reply<W: io::Write, R: ReadReply>(
+    w: &mut W,
+    r: &mut R,
+) -> Option<TerminalVersion> {
+    w.write_fmt(format_args!(concat!(csi!(">c"), csi!("c"))))
+        .ok()?;
+    w.flush().ok()?;
+    // => "\x1b[>${terminal};${version};${rom}c" (if supported) and "\x1b[?${attrs}c"
+    let mut params = None;
+    read_until_da1(r, |seq| {
+        if let Some(p) = reply_params(seq, b"\x1b[>", b'c') {
+            params = Some(p);
+        }
+    })?;
+    match params?[..] {
+        [terminal, version, rom, ..] => Some(TerminalVersion {
+            terminal,
+            version,
+            rom,
+        }),
+        [terminal, version] => Some(TerminalVersion {
+            terminal,
+            version,
+            rom: 0,
+        }),
+        _ => None,
+    }
+}
+
+/// DA3: tertiary device attributes
+/// Return the unit ID as reported, 8 hex digits.
+/// DA1 is sent after the query, so None is returned instead of blocking
+/// when the terminal doesn't support it.
+pub fn da3<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<String> {
+    query(w, r, da3_reply)
+}
+
+fn da3_reply<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<String> {
+    w.write_fmt(format_args!(concat!(csi!("=c"), csi!("c"))))
+        .ok()?;
+    w.flush().ok()?;
+    // => "\x1bP!|${id}\x1b\\"
+    let mut id = None;
+    read_until_da1(r, |seq| {
+        if let Some(payload) = string_payload(seq, b"\x1bP!|") {
+            id = Some(String::from_utf8_lossy(payload).into_owned());
+        }
+    })?;
+    id
+}
+
 /// SCP: save cursor position
 pub fn scp<W: io::Write>(w: &mut W) -> io::Result<()> {
     w.write_fmt(format_args!(csi!("s")))?;
@@ -306,6 +842,500 @@ pub fn decscusr<W: io::Write>(w: &mut W, s: DecscusrStyle) -> io::Result<()> {
     Ok(())
 }
 
+/// DECSET: DEC private mode set
+/// mode: http://ttssh2.osdn.jp/manual/ja/about/ctrlseq.html#mode
+pub fn decset<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("?{}h"), n))?;
+    Ok(())
+}
+
+/// DECRST: DEC private mode reset
+pub fn decrst<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("?{}l"), n))?;
+    Ok(())
+}
+
+/// DECARM: auto-repeat mode
+/// If enable is false, held keys are reported only once.
+pub fn decarm<W: io::Write>(w: &mut W, enable: bool) -> io::Result<()> {
+    if enable {
+        decset(w, 8)
+    } else {
+        decrst(w, 8)
+    }
+}
+
+/// DECTCEM: show cursor
+pub fn cursor_show<W: io::Write>(w: &mut W) -> io::Result<()> {
+    decset(w, 25)
+}
+
+/// DECTCEM: hide cursor
+pub fn cursor_hide<W: io::Write>(w: &mut W) -> io::Result<()> {
+    decrst(w, 25)
+}
+
+/// Switch to the alternate screen, saving the cursor and clearing the screen.
+/// The main screen and its scrollback are kept as they are.
+pub fn alt_screen_enter<W: io::Write>(w: &mut W) -> io::Result<()> {
+    alt_screen_enter_with(w, AltScreen::SwitchSaveCursor)
+}
+
+/// Switch back to the main screen and restore the cursor.
+pub fn alt_screen_leave<W: io::Write>(w: &mut W) -> io::Result<()> {
+    alt_screen_leave_with(w, AltScreen::SwitchSaveCursor)
+}
+
+/// Set the alternate screen mode m, for terminals which support only an older one.
+pub fn alt_screen_enter_with<W: io::Write>(w: &mut W, m: AltScreen) -> io::Result<()> {
+    decset(w, m as usize)
+}
+
+/// Reset the alternate screen mode m set by alt_screen_enter_with.
+pub fn alt_screen_leave_with<W: io::Write>(w: &mut W, m: AltScreen) -> io::Result<()> {
+    decrst(w, m as usize)
+}
+
+/// Bracketed paste mode
+/// Pasted text is enclosed in `CSI 200~` and `CSI 201~` while enabled.
+pub fn bracketed_paste<W: io::Write>(w: &mut W, enable: bool) -> io::Result<()> {
+    if enable {
+        decset(w, 2004)
+    } else {
+        decrst(w, 2004)
+    }
+}
+
+/// Enable mouse reporting of mode m.
+pub fn mouse_enable<W: io::Write>(w: &mut W, m: MouseMode) -> io::Result<()> {
+    decset(w, m as usize)
+}
+
+/// Disable mouse reporting of mode m.
+pub fn mouse_disable<W: io::Write>(w: &mut W, m: MouseMode) -> io::Result<()> {
+    decrst(w, m as usize)
+}
+
+/// Switch mouse reports to encoding e, or back to the legacy `CSI M` encoding.
+/// The legacy encoding can't report columns or rows beyond 223.
+pub fn mouse_encoding<W: io::Write>(w: &mut W, e: MouseEncoding, enable: bool) -> io::Result<()> {
+    if enable {
+        decset(w, e as usize)
+    } else {
+        decrst(w, e as usize)
+    }
+}
+
+/// Focus reporting mode
+/// The terminal sends `CSI I` when it gains focus and `CSI O` when it loses it.
+pub fn focus_reporting<W: io::Write>(w: &mut W, enable: bool) -> io::Result<()> {
+    if enable {
+        decset(w, 1004)
+    } else {
+        decrst(w, 1004)
+    }
+}
+
+/// DECCKM: cursor keys mode
+/// Arrow keys send `SS3 A` etc. when enabled, `CSI A` etc. otherwise.
+pub fn decckm<W: io::Write>(w: &mut W, application: bool) -> io::Result<()> {
+    if application {
+        decset(w, 1)
+    } else {
+        decrst(w, 1)
+    }
+}
+
+/// DECOM: origin mode
+/// Cursor addressing is relative to the scrolling region set by DECSTBM while enabled.
+pub fn origin_mode<W: io::Write>(w: &mut W, enable: bool) -> io::Result<()> {
+    if enable {
+        decset(w, 6)
+    } else {
+        decrst(w, 6)
+    }
+}
+
+/// DECSCNM: screen mode
+/// The whole screen is shown in reverse video while enabled, e.g. for a visual bell.
+pub fn reverse_video<W: io::Write>(w: &mut W, enable: bool) -> io::Result<()> {
+    if enable {
+        decset(w, 5)
+    } else {
+        decrst(w, 5)
+    }
+}
+
+/// DECELR: enable locator reports
+/// Coordinates are reported in pixels if pixels is true, otherwise in character cells.
+pub fn decelr<W: io::Write>(w: &mut W, mode: DecelrMode, pixels: bool) -> io::Result<()> {
+    let unit = if pixels { 1 } else { 2 };
+    w.write_fmt(format_args!(csi!("{};{}'z"), mode as usize, unit))?;
+    Ok(())
+}
+
+/// DECSLE: select locator events
+pub fn decsle<W: io::Write>(w: &mut W, events: &[DecsleEvent]) -> io::Result<()> {
+    let mut sep = "";
+    w.write_all(csi!().as_bytes())?;
+    for e in events {
+        w.write_fmt(format_args!("{}{}", sep, *e as usize))?;
+        sep = ";";
+    }
+    w.write_all(b"'{")?;
+    Ok(())
+}
+
+/// DECRQLP: request locator position
+/// The terminal answers with a DECLRP report, see input::parse_locator.
+pub fn decrqlp<W: io::Write>(w: &mut W) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("1'|")))?;
+    Ok(())
+}
+
+/// Move cursor relatively.
+/// Positive dx moves forward and positive dy moves down. Nothing is written for zero.
+pub fn move_by<W: io::Write>(w: &mut W, dx: isize, dy: isize) -> io::Result<()> {
+    if dy < 0 {
+        cuu(w, dy.unsigned_abs())?;
+    } else if dy > 0 {
+        cud(w, dy as usize)?;
+    }
+    if dx < 0 {
+        cub(w, dx.unsigned_abs())?;
+    } else if dx > 0 {
+        cuf(w, dx as usize)?;
+    }
+    Ok(())
+}
+
+/// Save cursor with SCP, move to (row, col), run f and restore cursor with RCP.
+pub fn at<W, F, T>(w: &mut W, row: usize, col: usize, f: F) -> io::Result<T>
+where
+    W: io::Write,
+    F: FnOnce(&mut W) -> io::Result<T>,
+{
+    at_with(w, CursorSave::Scp, row, col, f)
+}
+
+/// Same as `at` but the cursor is saved and restored in the way of s.
+/// The cursor is restored even if f fails.
+pub fn at_with<W, F, T>(w: &mut W, s: CursorSave, row: usize, col: usize, f: F) -> io::Result<T>
+where
+    W: io::Write,
+    F: FnOnce(&mut W) -> io::Result<T>,
+{
+    match s {
+        CursorSave::Scp => scp(w)?,
+        CursorSave::Decsc => decsc(w)?,
+    }
+    let ret = cup(w, row, col).and_then(|_| f(w));
+    match s {
+        CursorSave::Scp => rcp(w)?,
+        CursorSave::Decsc => decrc(w)?,
+    }
+    ret
+}
+
+/// Clear the current line and move cursor to the beginning of it.
+pub fn clear_current_line<W: io::Write>(w: &mut W) -> io::Result<()> {
+    el(w, ElClear::EntireLine)?;
+    cha(w, 1)
+}
+
+/// Clear n lines above the current line.
+/// Cursor is left at the beginning of the topmost cleared line, ready to rewrite them.
+/// CPL stops at the top margin, so with fewer than n lines above, only those are cleared
+/// and the cursor is left at the margin.
+pub fn clear_lines_above<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
+    for _ in 0..n {
+        cpl(w, 1)?;
+        el(w, ElClear::EntireLine)?;
+    }
+    Ok(())
+}
+
+/// Clear n lines below the current line.
+/// Cursor is moved back to the beginning of the current line.
+/// CNL stops at the bottom margin, so with fewer than n lines below, only those are cleared.
+/// The cursor is saved and restored with DECSC/DECRC to get back, which overwrites a cursor saved before.
+pub fn clear_lines_below<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
+    if n > 0 {
+        decsc(w)?;
+        for _ in 0..n {
+            cnl(w, 1)?;
+            el(w, ElClear::EntireLine)?;
+        }
+        decrc(w)?;
+    }
+    cha(w, 1)
+}
+
+/// DECSLPP: set lines per page
+/// Return an InvalidInput error if n < 24, as smaller values are window operations.
+pub fn decslpp<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
+    if n < 24 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "DECSLPP takes 24 lines or more",
+        ));
+    }
+    w.write_fmt(format_args!(csi!("{}t"), n))?;
+    Ok(())
+}
+
+/// Request to resize the text area to rows x cols characters.
+pub fn resize<W: io::Write>(w: &mut W, rows: usize, cols: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("8;{};{}t"), rows, cols))?;
+    Ok(())
+}
+
+/// Request to resize the text area to height x width pixels.
+pub fn resize_pixels<W: io::Write>(w: &mut W, height: usize, width: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("4;{};{}t"), height, width))?;
+    Ok(())
+}
+
+/// Report size of the text area in characters.
+/// return (rows, cols)
+/// DA1 is sent after the query, so None is returned instead of blocking
+/// when the terminal doesn't report it, e.g. with window operations disabled.
+pub fn text_area_size<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<(usize, usize)> {
+    query(w, r, |w, r| window_report(w, r, 18, 8))
+}
+
+/// Report size of the text area in pixels.
+/// return (height, width)
+/// DA1 is sent after the query, so None is returned instead of blocking when it's not reported.
+pub fn text_area_size_pixels<W: io::Write, R: ReadReply>(
+    w: &mut W,
+    r: &mut R,
+) -> Option<(usize, usize)> {
+    query(w, r, |w, r| window_report(w, r, 14, 4))
+}
+
+// => "\x1b[${kind};${a};${b}t" (if supported) and "\x1b[?${attrs}c"
+fn window_report<W: io::Write, R: ReadReply>(
+    w: &mut W,
+    r: &mut R,
+    req: usize,
+    kind: usize,
+) -> Option<(usize, usize)> {
+    w.write_fmt(format_args!(concat!(csi!("{}t"), csi!("c")), req))
+        .ok()?;
+    w.flush().ok()?;
+    let mut size = None;
+    read_until_da1(r, |seq| {
+        // reports of other kinds are skipped
+        if let Some(params) = reply_params(seq, b"\x1b[", b't') {
+            if let [k, a, b] = params[..] {
+                if k == kind {
+                    size = Some((a, b));
+                }
+            }
+        }
+    })?;
+    size
+}
+
+/// XTSMGRAPHICS: set or request graphics attribute
+/// Return values reported for item, that is the number of color registers or (width, height) of geometry.
+/// DA1 is sent after the query, so None is returned instead of blocking
+/// when the terminal doesn't support it or reports an error.
+pub fn xtsmgraphics<W: io::Write, R: ReadReply>(
+    w: &mut W,
+    r: &mut R,
+    item: GraphicsItem,
+    action: GraphicsAction,
+) -> Option<Vec<usize>> {
+    query(w, r, |w, r| xtsmgraphics_reply(w, r, item, action))
+}
+
+/// Report maximum sixel geometry and the number of color registers.
+pub fn graphics_limits<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<GraphicsLimits> {
+    query(w, r, |w, r| {
+        let colors =
+            xtsmgraphics_reply(w, r, GraphicsItem::ColorRegisters, GraphicsAction::ReadMax)?;
+        let geometry =
+            xtsmgraphics_reply(w, r, GraphicsItem::SixelGeometry, GraphicsAction::ReadMax)?;
+        match (&colors[..], &geometry[..]) {
+            ([c], [width, height]) => Some(GraphicsLimits {
+                color_registers: *c,
+                sixel_width: *width,
+                sixel_height: *height,
+            }),
+            _ => None,
+        }
+    })
+}
+
+// => "\x1b[?${item};${status};${values}S" (if supported) and "\x1b[?${attrs}c"
+fn xtsmgraphics_reply<W: io::Write, R: ReadReply>(
+    w: &mut W,
+    r: &mut R,
+    item: GraphicsItem,
+    action: GraphicsAction,
+) -> Option<Vec<usize>> {
+    let item = item as usize;
+    w.write_fmt(format_args!(
+        concat!(csi!("?{};{}S"), csi!("c")),
+        item, action as usize
+    ))
+    .ok()?;
+    w.flush().ok()?;
+    let mut values = None;
+    read_until_da1(r, |seq| {
+        if let Some(params) = reply_params(seq, b"\x1b[?", b'S') {
+            if let [i, 0, ref v @ ..] = params[..] {
+                if i == item {
+                    values = Some(v.to_vec());
+                }
+            }
+        }
+    })?;
+    values
+}
+
+/// Reset visual states to the known ones.
+/// Switch to the main screen, reset scrolling region, turn on autowrap,
+/// show cursor with the default style and reset SGR.
+/// Useful at exit or after a child process which may leave the terminal in a weird state.
+pub fn full_visual_reset<W: io::Write>(w: &mut W) -> io::Result<()> {
+    alt_screen_leave(w)?;
+    decstbm(w, None, None)?;
+    decset(w, 7)?;
+    cursor_show(w)?;
+    w.write_fmt(format_args!(csi!("0 q")))?;
+    sgr(w, SgrCode::Normal)
+}
+
+/// DECSTR: soft terminal reset
+/// Reset modes such as autowrap, origin mode, margins and SGR without clearing the screen.
+pub fn decstr<W: io::Write>(w: &mut W) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("!p")))?;
+    Ok(())
+}
+
+/// Move cursor from `from` to `to`, both (row, col), with the fewest bytes like curses' mvcur.
+/// CUP, CHA, CUU/CUD, CUF/CUB, CR, BS and CR LF are considered.
+/// The cursor must really be at `from`, and CR LF is assumed to move to the next line.
+pub fn move_to<W: io::Write>(
+    w: &mut W,
+    from: (usize, usize),
+    to: (usize, usize),
+) -> io::Result<()> {
+    let (from, to) = ((_nz(from.0), _nz(from.1)), (_nz(to.0), _nz(to.1)));
+    let mut best = Vec::new();
+    cup(&mut best, to.0, to.1)?;
+
+    let mut cand = Vec::new();
+    move_by(&mut cand, 0, to.0 as isize - from.0 as isize)?;
+    move_horizontal(&mut cand, from.1, to.1)?;
+    if cand.len() < best.len() {
+        best = cand;
+    }
+    if to.0 > from.0 {
+        let mut cand = b"\r\n".repeat(to.0 - from.0);
+        move_horizontal(&mut cand, 1, to.1)?;
+        if cand.len() < best.len() {
+            best = cand;
+        }
+    }
+    w.write_all(&best)
+}
+
+// write the cheapest horizontal movement in the same line
+fn move_horizontal<W: io::Write>(w: &mut W, from: usize, to: usize) -> io::Result<()> {
+    if from == to {
+        return Ok(());
+    }
+    let mut cands = Vec::new();
+    let mut cand = Vec::new();
+    cha(&mut cand, to)?;
+    cands.push(cand);
+    let mut cand = Vec::new();
+    move_by(&mut cand, to as isize - from as isize, 0)?;
+    cands.push(cand);
+    if to < from {
+        cands.push(vec![0x08; from - to]);
+    }
+    let mut cand = b"\r".to_vec();
+    move_by(&mut cand, to as isize - 1, 0)?;
+    cands.push(cand);
+    let best = cands
+        .into_iter()
+        .min_by_key(|c| c.len())
+        .unwrap_or_default();
+    w.write_all(&best)
+}
+
+/// DECSTBM: set top and bottom margins
+/// Scrolling is confined to the lines from top to bottom.
+/// Omitted margin means the first or last line, so `decstbm(w, None, None)` resets margins.
+pub fn decstbm<W: io::Write>(
+    w: &mut W,
+    top: Option<usize>,
+    bottom: Option<usize>,
+) -> io::Result<()> {
+    match (top, bottom) {
+        (None, None) => w.write_fmt(format_args!(csi!("r")))?,
+        (Some(t), None) => w.write_fmt(format_args!(csi!("{}r"), _nz(t)))?,
+        (None, Some(b)) => w.write_fmt(format_args!(csi!(";{}r"), _nz(b)))?,
+        (Some(t), Some(b)) => w.write_fmt(format_args!(csi!("{};{}r"), _nz(t), _nz(b)))?,
+    }
+    Ok(())
+}
+
+/// DECSSDT: select status display type
+pub fn decssdt<W: io::Write>(w: &mut W, t: DecssdtType) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("{}$~"), t as usize))?;
+    Ok(())
+}
+
+/// DECSASD: select active status display
+/// Output goes to the status line while it's active.
+pub fn decsasd<W: io::Write>(w: &mut W, d: DecsasdDisplay) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("{}$}}"), d as usize))?;
+    Ok(())
+}
+
+/// Replace the status line with text, keeping the cursor.
+/// StatusLine::Pinned is the fallback for terminals without DEC status display.
+pub fn status_line<W: io::Write>(w: &mut W, s: StatusLine, text: &str) -> io::Result<()> {
+    match s {
+        StatusLine::Dec => {
+            decssdt(w, DecssdtType::HostWritable)?;
+            decsasd(w, DecsasdDisplay::StatusLine)?;
+            el(w, ElClear::EntireLine)?;
+            w.write_all(text.as_bytes())?;
+            decsasd(w, DecsasdDisplay::Main)
+        }
+        StatusLine::Pinned(rows) => {
+            // DECSTBM homes the cursor, so save it first
+            decsc(w)?;
+            decstbm(w, None, Some(rows.saturating_sub(1)))?;
+            cup(w, rows, 1)?;
+            el(w, ElClear::EntireLine)?;
+            w.write_all(text.as_bytes())?;
+            decrc(w)
+        }
+    }
+}
+
+/// Remove the status line set by status_line.
+pub fn status_line_reset<W: io::Write>(w: &mut W, s: StatusLine) -> io::Result<()> {
+    match s {
+        StatusLine::Dec => decssdt(w, DecssdtType::None),
+        StatusLine::Pinned(rows) => {
+            decsc(w)?;
+            decstbm(w, None, None)?;
+            cup(w, rows, 1)?;
+            el(w, ElClear::EntireLine)?;
+            decrc(w)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -592,4 +1622,447 @@ mod tests {
         w.flush().unwrap();
         teardown(&mut w);
     }
+
+    #[test]
+    fn test_decarm() {
+        let mut w = Vec::new();
+        decarm(&mut w, false).unwrap();
+        decarm(&mut w, true).unwrap();
+        assert_eq!(w, b"\x1b[?8l\x1b[?8h");
+    }
+
+    #[test]
+    fn test_move_by() {
+        let mut w = Vec::new();
+        move_by(&mut w, 0, 0).unwrap();
+        assert_eq!(w, b"");
+        move_by(&mut w, 3, -2).unwrap();
+        assert_eq!(w, b"\x1b[2A\x1b[3C");
+        w.clear();
+        move_by(&mut w, -1, 4).unwrap();
+        assert_eq!(w, b"\x1b[4B\x1b[1D");
+    }
+
+    #[test]
+    fn test_at() {
+        let mut w = Vec::new();
+        at(&mut w, 1, 70, |w| w.write_all(b"status")).unwrap();
+        assert_eq!(w, b"\x1b[s\x1b[1;70Hstatus\x1b[u");
+        w.clear();
+        at_with(&mut w, CursorSave::Decsc, 2, 3, |w| w.write_all(b"x")).unwrap();
+        assert_eq!(w, b"\x1b7\x1b[2;3Hx\x1b8");
+    }
+
+    #[test]
+    fn test_clear_lines() {
+        let mut w = Vec::new();
+        clear_current_line(&mut w).unwrap();
+        assert_eq!(w, b"\x1b[2K\x1b[1G");
+        w.clear();
+        clear_lines_above(&mut w, 2).unwrap();
+        assert_eq!(w, b"\x1b[1F\x1b[2K\x1b[1F\x1b[2K");
+        w.clear();
+        clear_lines_below(&mut w, 2).unwrap();
+        assert_eq!(w, &b"\x1b7\x1b[1E\x1b[2K\x1b[1E\x1b[2K\x1b8\x1b[1G"[..]);
+        w.clear();
+        clear_lines_below(&mut w, 0).unwrap();
+        assert_eq!(w, b"\x1b[1G");
+        w.clear();
+        clear_lines_above(&mut w, 0).unwrap();
+        assert_eq!(w, b"");
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut w = Vec::new();
+        resize(&mut w, 24, 80).unwrap();
+        resize_pixels(&mut w, 480, 640).unwrap();
+        assert!(decslpp(&mut w, 10).is_err());
+        decslpp(&mut w, 36).unwrap();
+        assert_eq!(w, b"\x1b[8;24;80t\x1b[4;480;640t\x1b[36t");
+
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1b[8;24;80t\x1b[?62c";
+        assert_eq!(window_report(&mut w, &mut r, 18, 8), Some((24, 80)));
+        assert_eq!(w, b"\x1b[18t\x1b[c");
+        let mut r: &[u8] = b"\x1b[4;480;640t\x1b[?62c";
+        assert_eq!(window_report(&mut w, &mut r, 14, 4), Some((480, 640)));
+        // a report of another kind is skipped
+        let mut r: &[u8] = b"\x1b[4;480;640t\x1b[8;24;80t\x1b[?62c";
+        assert_eq!(window_report(&mut w, &mut r, 18, 8), Some((24, 80)));
+        // window operations disabled
+        let mut r: &[u8] = b"\x1b[?62c\x1b[8;24;80t";
+        assert_eq!(window_report(&mut w, &mut r, 18, 8), None);
+    }
+
+    #[test]
+    fn test_xtsmgraphics() {
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1b[?2;0;1000;1000S\x1b[?62;4c";
+        assert_eq!(
+            xtsmgraphics_reply(
+                &mut w,
+                &mut r,
+                GraphicsItem::SixelGeometry,
+                GraphicsAction::ReadMax
+            ),
+            Some(vec![1000, 1000])
+        );
+        assert_eq!(w, b"\x1b[?2;4S\x1b[c");
+        let mut r: &[u8] = b"\x1b[?1;3;0S\x1b[?62;4c";
+        assert_eq!(
+            xtsmgraphics_reply(
+                &mut w,
+                &mut r,
+                GraphicsItem::ColorRegisters,
+                GraphicsAction::Read
+            ),
+            None
+        );
+        let mut r: &[u8] = b"\x1b[?62;22c";
+        assert_eq!(
+            xtsmgraphics_reply(
+                &mut w,
+                &mut r,
+                GraphicsItem::ColorRegisters,
+                GraphicsAction::Read
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_full_visual_reset() {
+        let mut w = Vec::new();
+        full_visual_reset(&mut w).unwrap();
+        assert_eq!(w, &b"\x1b[?1049l\x1b[r\x1b[?7h\x1b[?25h\x1b[0 q\x1b[0m"[..]);
+    }
+
+    #[test]
+    fn test_params() {
+        let v: Vec<Option<usize>> = params(b"1;;38:2::10:20:30").map(|p| p.value()).collect();
+        assert_eq!(v, vec![Some(1), None, Some(38)]);
+        let p = params(b"1;;38:2::10:20:30").nth(2).unwrap();
+        let sub: Vec<Option<usize>> = p.subparams().collect();
+        assert_eq!(
+            sub,
+            vec![Some(38), Some(2), None, Some(10), Some(20), Some(30)]
+        );
+        assert_eq!(params(b"").count(), 0);
+        assert_eq!(params(b";").count(), 2);
+        assert_eq!(params(b"?1").next().unwrap().value_or(7), 7);
+    }
+
+    #[test]
+    fn test_read_reply() {
+        let mut r: &[u8] = b"x\x1b[?1;2c\x1b]l\x1b[\x07\x1bP1$r\x1b\\";
+        let seq = |s: &[u8]| Some(Reply::Sequence(s.to_vec()));
+        assert_eq!(r.read_reply(), seq(b"\x1b[?1;2c"));
+        assert_eq!(r.read_reply(), seq(b"\x1b]l\x1b[\x07"));
+        assert_eq!(r.read_reply(), seq(b"\x1bP1$r\x1b\\"));
+        assert_eq!(r.read_reply(), None);
+
+        let mut long = b"\x1b]l".to_vec();
+        long.extend(vec![b'x'; 300]);
+        long.extend(b"\x1b\\\x1b[?1c");
+        let mut r = ReplyReader::new(&long[..]).max_len(4);
+        match r.read_reply() {
+            Some(Reply::Truncated(head)) => assert_eq!(head.len(), MIN_REPLY_MAX_LEN),
+            reply => panic!("unexpected {:?}", reply),
+        }
+        assert_eq!(r.read_reply(), seq(b"\x1b[?1c"));
+        assert_eq!(r.truncated(), 1);
+        let mut r = ReplyReader::new(&long[..]).max_len(1000);
+        assert!(matches!(r.read_reply(), Some(Reply::Sequence(_))));
+        assert_eq!(r.truncated(), 0);
+
+        // parameters never outgrow the sequence, and a flood ends the query instead of blocking
+        let mut flood = b"\x1b[>".to_vec();
+        flood.extend(b"1;".repeat(DEFAULT_REPLY_MAX_LEN));
+        flood.extend(b"c\x1b[>41;390;0c\x1b[?1c");
+        let mut r = &flood[..];
+        let mut w = Vec::new();
+        assert_eq!(da2_reply(&mut w, &mut r), None);
+
+        // a truncated DA1 ends the query
+        let mut da1 = b"\x1b[?".to_vec();
+        da1.extend(b"1;".repeat(MIN_REPLY_MAX_LEN));
+        da1.extend(b"c\x1bP!|00000000\x1b\\\x1b[?1c");
+        let mut r = ReplyReader::new(&da1[..]).max_len(0);
+        assert_eq!(da3_reply(&mut w, &mut r), None);
+        assert_eq!(r.truncated(), 1);
+    }
+
+    #[test]
+    fn test_reply_params() {
+        assert_eq!(
+            reply_params(b"\x1b[?64;1;;4c", b"\x1b[?", b'c'),
+            Some(vec![64, 1, 0, 4])
+        );
+        assert_eq!(
+            reply_params(b"\x1b[8;24;80t", b"\x1b[", b't'),
+            Some(vec![8, 24, 80])
+        );
+        assert_eq!(reply_params(b"\x1b[8;24;80t", b"\x1b[?", b't'), None);
+        assert_eq!(reply_params(b"\x1b[?1;2S", b"\x1b[?", b'c'), None);
+    }
+
+    #[test]
+    fn test_move_to() {
+        let move_to = |from, to| {
+            let mut w = Vec::new();
+            super::move_to(&mut w, from, to).unwrap();
+            w
+        };
+        assert_eq!(move_to((3, 5), (3, 5)), b"");
+        assert_eq!(move_to((3, 5), (3, 1)), b"\r");
+        assert_eq!(move_to((3, 5), (3, 3)), b"\x08\x08");
+        assert_eq!(move_to((3, 50), (3, 40)), b"\x1b[40G");
+        assert_eq!(move_to((3, 5), (4, 1)), b"\r\n");
+        assert_eq!(move_to((3, 5), (4, 5)), b"\x1b[1B");
+        assert_eq!(move_to((3, 5), (2, 6)), b"\x1b[2;6H");
+        assert_eq!(move_to((3, 5), (2, 4)), b"\x1b[1A\x08");
+        assert_eq!(move_to((1, 1), (20, 70)), b"\x1b[20;70H");
+    }
+
+    #[test]
+    fn test_decstbm() {
+        let mut w = Vec::new();
+        decstbm(&mut w, Some(1), Some(23)).unwrap();
+        decstbm(&mut w, Some(2), None).unwrap();
+        decstbm(&mut w, None, Some(10)).unwrap();
+        decstbm(&mut w, None, None).unwrap();
+        assert_eq!(w, b"\x1b[1;23r\x1b[2r\x1b[;10r\x1b[r");
+    }
+
+    #[test]
+    fn test_il() {
+        let mut w = Vec::new();
+        il(&mut w, 3).unwrap();
+        il(&mut w, 0).unwrap();
+        assert_eq!(w, b"\x1b[3L\x1b[1L");
+    }
+
+    #[test]
+    fn test_dl() {
+        let mut w = Vec::new();
+        dl(&mut w, 2).unwrap();
+        dl(&mut w, 0).unwrap();
+        assert_eq!(w, b"\x1b[2M\x1b[1M");
+    }
+
+    #[test]
+    fn test_ech() {
+        let mut w = Vec::new();
+        ech(&mut w, 5).unwrap();
+        ech(&mut w, 0).unwrap();
+        assert_eq!(w, b"\x1b[5X\x1b[1X");
+    }
+
+    #[test]
+    fn test_rep() {
+        let mut w = Vec::new();
+        w.push(b'-');
+        rep(&mut w, 79).unwrap();
+        assert_eq!(w, b"-\x1b[79b");
+    }
+
+    #[test]
+    fn test_tbc() {
+        let mut w = Vec::new();
+        tbc(&mut w, TbcClear::AllColumns).unwrap();
+        crate::esc::hts(&mut w).unwrap();
+        tbc(&mut w, TbcClear::CurrentColumn).unwrap();
+        assert_eq!(w, b"\x1b[3g\x1bH\x1b[0g");
+    }
+
+    #[test]
+    fn test_vpa() {
+        let mut w = Vec::new();
+        vpa(&mut w, 12).unwrap();
+        vpa(&mut w, 0).unwrap();
+        assert_eq!(w, b"\x1b[12d\x1b[1d");
+    }
+
+    #[test]
+    fn test_vpr() {
+        let mut w = Vec::new();
+        vpr(&mut w, 4).unwrap();
+        vpr(&mut w, 0).unwrap();
+        assert_eq!(w, b"\x1b[4e\x1b[1e");
+    }
+
+    #[test]
+    fn test_hpr() {
+        let mut w = Vec::new();
+        hpr(&mut w, 8).unwrap();
+        hpr(&mut w, 0).unwrap();
+        assert_eq!(w, b"\x1b[8a\x1b[1a");
+    }
+
+    #[test]
+    fn test_locator() {
+        let mut w = Vec::new();
+        decelr(&mut w, DecelrMode::Enable, false).unwrap();
+        decsle(&mut w, &[DecsleEvent::ButtonDown, DecsleEvent::ButtonUp]).unwrap();
+        decrqlp(&mut w).unwrap();
+        decelr(&mut w, DecelrMode::Disable, false).unwrap();
+        assert_eq!(w, &b"\x1b[1;2'z\x1b[1;3'{\x1b[1'|\x1b[0;2'z"[..]);
+    }
+
+    #[test]
+    fn test_hvp() {
+        let mut w = Vec::new();
+        hvp(&mut w, 3, 7).unwrap();
+        hvp(&mut w, 0, 0).unwrap();
+        assert_eq!(w, b"\x1b[3;7f\x1b[1;1f");
+    }
+
+    #[test]
+    fn test_da1() {
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1b[?64;1;2;4;6;22;28c";
+        let da = da1_reply(&mut w, &mut r).unwrap();
+        assert_eq!(w, b"\x1b[c");
+        assert_eq!(da.class, 64);
+        assert!(da.columns_132() && da.sixel() && da.ansi_color() && da.rectangular_editing());
+        assert!(!da.regis() && !da.locator());
+        let mut r: &[u8] = b"\x1b[?6c";
+        assert_eq!(
+            da1_reply(&mut w, &mut r),
+            Some(DeviceAttributes {
+                class: 6,
+                features: vec![]
+            })
+        );
+    }
+
+    #[test]
+    fn test_status_line() {
+        let mut w = Vec::new();
+        status_line(&mut w, StatusLine::Dec, "ok").unwrap();
+        status_line_reset(&mut w, StatusLine::Dec).unwrap();
+        assert_eq!(w, &b"\x1b[2$~\x1b[1$}\x1b[2Kok\x1b[0$}\x1b[0$~"[..]);
+        let mut w = Vec::new();
+        status_line(&mut w, StatusLine::Pinned(24), "ok").unwrap();
+        status_line_reset(&mut w, StatusLine::Pinned(24)).unwrap();
+        assert_eq!(
+            w,
+            &b"\x1b7\x1b[;23r\x1b[24;1H\x1b[2Kok\x1b8\x1b7\x1b[r\x1b[24;1H\x1b[2K\x1b8"[..]
+        );
+    }
+
+    #[test]
+    fn test_da2_da3() {
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1b[>41;390;0c\x1b[?65;1c";
+        assert_eq!(
+            da2_reply(&mut w, &mut r),
+            Some(TerminalVersion {
+                terminal: 41,
+                version: 390,
+                rom: 0
+            })
+        );
+        assert_eq!(w, b"\x1b[>c\x1b[c");
+        // DA2 not answered
+        let mut r: &[u8] = b"\x1b[?65;1c\x1b[>41;390;0c";
+        assert_eq!(da2_reply(&mut w, &mut r), None);
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1bP!|7E565445\x1b\\\x1b[?65;1c";
+        assert_eq!(da3_reply(&mut w, &mut r), Some("7E565445".to_string()));
+        assert_eq!(w, b"\x1b[=c\x1b[c");
+        let mut r: &[u8] = b"\x1b[?62;22c";
+        assert_eq!(da3_reply(&mut w, &mut r), None);
+    }
+
+    #[test]
+    fn test_decstr() {
+        let mut w = Vec::new();
+        decstr(&mut w).unwrap();
+        assert_eq!(w, b"\x1b[!p");
+    }
+
+    #[test]
+    fn test_cursor_show_hide() {
+        let mut w = Vec::new();
+        cursor_hide(&mut w).unwrap();
+        cursor_show(&mut w).unwrap();
+        assert_eq!(w, b"\x1b[?25l\x1b[?25h");
+    }
+
+    #[test]
+    fn test_alt_screen() {
+        let mut w = Vec::new();
+        alt_screen_enter(&mut w).unwrap();
+        alt_screen_leave(&mut w).unwrap();
+        assert_eq!(w, b"\x1b[?1049h\x1b[?1049l");
+    }
+
+    #[test]
+    fn test_alt_screen_with() {
+        let mut w = Vec::new();
+        alt_screen_enter_with(&mut w, AltScreen::Switch).unwrap();
+        alt_screen_leave_with(&mut w, AltScreen::Switch).unwrap();
+        alt_screen_enter_with(&mut w, AltScreen::SaveCursor).unwrap();
+        alt_screen_leave_with(&mut w, AltScreen::SwitchClear).unwrap();
+        assert_eq!(w, &b"\x1b[?47h\x1b[?47l\x1b[?1048h\x1b[?1047l"[..]);
+    }
+
+    #[test]
+    fn test_bracketed_paste() {
+        let mut w = Vec::new();
+        bracketed_paste(&mut w, true).unwrap();
+        bracketed_paste(&mut w, false).unwrap();
+        assert_eq!(w, b"\x1b[?2004h\x1b[?2004l");
+    }
+
+    #[test]
+    fn test_mouse_mode() {
+        let mut w = Vec::new();
+        mouse_enable(&mut w, MouseMode::Click).unwrap();
+        mouse_enable(&mut w, MouseMode::Drag).unwrap();
+        mouse_disable(&mut w, MouseMode::AnyMotion).unwrap();
+        assert_eq!(w, &b"\x1b[?1000h\x1b[?1002h\x1b[?1003l"[..]);
+    }
+
+    #[test]
+    fn test_mouse_encoding() {
+        let mut w = Vec::new();
+        mouse_encoding(&mut w, MouseEncoding::Sgr, true).unwrap();
+        mouse_encoding(&mut w, MouseEncoding::SgrPixels, false).unwrap();
+        assert_eq!(w, b"\x1b[?1006h\x1b[?1016l");
+    }
+
+    #[test]
+    fn test_focus_reporting() {
+        let mut w = Vec::new();
+        focus_reporting(&mut w, true).unwrap();
+        focus_reporting(&mut w, false).unwrap();
+        assert_eq!(w, b"\x1b[?1004h\x1b[?1004l");
+    }
+
+    #[test]
+    fn test_decckm() {
+        let mut w = Vec::new();
+        decckm(&mut w, true).unwrap();
+        decckm(&mut w, false).unwrap();
+        assert_eq!(w, b"\x1b[?1h\x1b[?1l");
+    }
+
+    #[test]
+    fn test_origin_mode() {
+        let mut w = Vec::new();
+        origin_mode(&mut w, true).unwrap();
+        origin_mode(&mut w, false).unwrap();
+        assert_eq!(w, b"\x1b[?6h\x1b[?6l");
+    }
+
+    #[test]
+    fn test_reverse_video() {
+        let mut w = Vec::new();
+        reverse_video(&mut w, true).unwrap();
+        reverse_video(&mut w, false).unwrap();
+        assert_eq!(w, b"\x1b[?5h\x1b[?5l");
+    }
 }
@@ -0,0 +1,223 @@
+// DCS(Device Control String) of Escape sequence
+
+use crate::csi::{query, read_until_da1, string_payload, ReadReply};
+use std::io;
+
+#[macro_export]
+macro_rules! dcs {
+    ($( $s:expr ),*) => { concat!("\x1bP", $( $s ),*, "\x1b\\") };
+}
+
+/// Cursor information report (DECCIR).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorInfo {
+    pub row: usize,
+    pub col: usize,
+    pub page: usize,
+    pub bold: bool,
+    pub underline: bool,
+    pub blink: bool,
+    pub reverse: bool,
+    pub selective_erase: bool,
+    pub origin_mode: bool,
+    pub single_shift_2: bool,
+    pub single_shift_3: bool,
+    pub wrap_pending: bool,
+    pub gl: usize,
+    pub gr: usize,
+    pub charset_size: u8,
+    pub charsets: String,
+}
+
+/// DECRQSS: request selection or setting
+/// setting is the final characters of the control function, e.g. "m" for SGR, " q" for DECSCUSR.
+/// Return the reported control function without CSI, e.g. "0;1m".
+/// DA1 is sent after the request, so None is returned instead of blocking
+/// when the terminal doesn't support it or the setting is invalid.
+pub fn decrqss<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R, setting: &str) -> Option<String> {
+    query(w, r, |w, r| decrqss_reply(w, r, setting))
+}
+
+/// Check whether the terminal really honors 24-bit color rather than approximating it.
+/// A truecolor is set with SGR and read back by DECRQSS, then SGR is restored.
+/// Return None if DECRQSS is not supported.
+pub fn truecolor<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<bool> {
+    query(w, r, truecolor_reply)
+}
+
+/// DECRQPSR: request cursor information report (DECCIR)
+/// DA1 is sent after the request, so None is returned instead of blocking when it's not supported.
+pub fn deccir<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<CursorInfo> {
+    query(w, r, |w, r| {
+        let s = decrqpsr_reply(w, r, 1)?;
+        parse_deccir(&s)
+    })
+}
+
+/// DECRQPSR: request tab stop report (DECTABSR)
+/// Return columns of the tab stops.
+/// DA1 is sent after the request, so None is returned instead of blocking when it's not supported.
+pub fn dectabsr<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<Vec<usize>> {
+    query(w, r, |w, r| {
+        let s = decrqpsr_reply(w, r, 2)?;
+        parse_dectabsr(&s)
+    })
+}
+
+// => "\x1bP${n}$u${report}\x1b\\" (if supported) and "\x1b[?${attrs}c"
+fn decrqpsr_reply<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R, n: usize) -> Option<String> {
+    w.write_fmt(format_args!(concat!(csi!("{}$w"), csi!("c")), n))
+        .ok()?;
+    w.flush().ok()?;
+    let prefix = format!("\x1bP{}$u", n);
+    let mut reply = None;
+    read_until_da1(r, |seq| {
+        if let Some(s) = string_payload(seq, prefix.as_bytes()) {
+            reply = Some(String::from_utf8_lossy(s).into_owned());
+        }
+    })?;
+    reply
+}
+
+// Pr;Pc;Pp;Srend;Satt;Sflag;Pgl;Pgr;Scss;Sdesig
+fn parse_deccir(s: &str) -> Option<CursorInfo> {
+    let f: Vec<&str> = s.splitn(10, ';').collect();
+    if f.len() != 10 {
+        return None;
+    }
+    // flags are encoded as 0x40 + bits
+    let flag = |s: &str| -> Option<u8> {
+        match s.as_bytes() {
+            [b @ 0x40..=0x7f] => Some(b - 0x40),
+            _ => None,
+        }
+    };
+    let (rend, att, flags) = (flag(f[3])?, flag(f[4])?, flag(f[5])?);
+    Some(CursorInfo {
+        row: f[0].parse().ok()?,
+        col: f[1].parse().ok()?,
+        page: f[2].parse().ok()?,
+        bold: rend & 1 != 0,
+        underline: rend & 2 != 0,
+        blink: rend & 4 != 0,
+        reverse: rend & 8 != 0,
+        selective_erase: att & 1 != 0,
+        origin_mode: flags & 1 != 0,
+        single_shift_2: flags & 2 != 0,
+        single_shift_3: flags & 4 != 0,
+        wrap_pending: flags & 8 != 0,
+        gl: f[6].parse().ok()?,
+        gr: f[7].parse().ok()?,
+        charset_size: flag(f[8])?,
+        charsets: f[9].to_string(),
+    })
+}
+
+// tab stops separated by '/'
+fn parse_dectabsr(s: &str) -> Option<Vec<usize>> {
+    if s.is_empty() {
+        return Some(Vec::new());
+    }
+    s.split('/').map(|t| t.parse().ok()).collect()
+}
+
+// => "\x1bP1$r${setting}\x1b\\" (if valid) and "\x1b[?${attrs}c"
+fn decrqss_reply<W: io::Write, R: ReadReply>(
+    w: &mut W,
+    r: &mut R,
+    setting: &str,
+) -> Option<String> {
+    w.write_fmt(format_args!(concat!(dcs!("$q{}"), csi!("c")), setting))
+        .ok()?;
+    w.flush().ok()?;
+    let mut reply = None;
+    read_until_da1(r, |seq| {
+        if let Some(s) = string_payload(seq, b"\x1bP1$r") {
+            reply = Some(String::from_utf8_lossy(s).into_owned());
+        }
+    })?;
+    reply
+}
+
+fn truecolor_reply<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<bool> {
+    let saved = decrqss_reply(w, r, "m")?;
+    w.write_fmt(format_args!(csi!("38;2;10;20;30m"))).ok()?;
+    let reply = decrqss_reply(w, r, "m");
+    w.write_fmt(format_args!(csi!("0;{}"), saved)).ok()?;
+    w.flush().ok()?;
+    let reply = reply?;
+    Some(
+        ["38;2;10;20;30", "38:2:10:20:30", "38:2::10:20:30"]
+            .iter()
+            .any(|s| reply.contains(s)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decrqss() {
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1bP1$r2 q\x1b\\\x1b[?62;22c";
+        assert_eq!(decrqss_reply(&mut w, &mut r, " q"), Some("2 q".to_string()));
+        assert_eq!(w, b"\x1bP$q q\x1b\\\x1b[c");
+        let mut r: &[u8] = b"\x1bP0$r\x1b\\\x1b[?62;22c";
+        assert_eq!(decrqss_reply(&mut w, &mut r, "x"), None);
+    }
+
+    #[test]
+    fn test_truecolor() {
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1bP1$r0;1m\x1b\\\x1b[?62c\x1bP1$r0;1;38:2::10:20:30m\x1b\\\x1b[?62c";
+        assert_eq!(truecolor_reply(&mut w, &mut r), Some(true));
+        assert!(w.ends_with(b"\x1b[0;0;1m"));
+        let mut r: &[u8] = b"\x1bP1$r0m\x1b\\\x1b[?62c\x1bP1$r0;38;5;234m\x1b\\\x1b[?62c";
+        assert_eq!(truecolor_reply(&mut w, &mut r), Some(false));
+        let mut r: &[u8] = b"\x1b[?62c";
+        assert_eq!(truecolor_reply(&mut w, &mut r), None);
+    }
+
+    #[test]
+    fn test_deccir() {
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1bP1$u3;5;1;I;@;I;0;2;@;BB%5\x1b\\\x1b[?62c";
+        let s = decrqpsr_reply(&mut w, &mut r, 1).unwrap();
+        assert_eq!(w, b"\x1b[1$w\x1b[c");
+        assert_eq!(
+            parse_deccir(&s),
+            Some(CursorInfo {
+                row: 3,
+                col: 5,
+                page: 1,
+                bold: true,
+                underline: false,
+                blink: false,
+                reverse: true,
+                selective_erase: false,
+                origin_mode: true,
+                single_shift_2: false,
+                single_shift_3: false,
+                wrap_pending: true,
+                gl: 0,
+                gr: 2,
+                charset_size: 0,
+                charsets: "BB%5".to_string(),
+            })
+        );
+        assert_eq!(parse_deccir("3;5;1"), None);
+    }
+
+    #[test]
+    fn test_dectabsr() {
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1bP2$u9/17/25\x1b\\\x1b[?62c";
+        let s = decrqpsr_reply(&mut w, &mut r, 2).unwrap();
+        assert_eq!(w, b"\x1b[2$w\x1b[c");
+        assert_eq!(parse_dectabsr(&s), Some(vec![9, 17, 25]));
+        assert_eq!(parse_dectabsr(""), Some(vec![]));
+        let mut r: &[u8] = b"\x1b[?62c";
+        assert_eq!(decrqpsr_reply(&mut w, &mut r, 2), None);
+    }
+}
@@ -0,0 +1,122 @@
+// Detection of the environment the terminal runs in
+
+use crate::csi::{da2_reply, query, ReadReply};
+use std::env;
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+    Tmux,
+    Screen,
+    Zellij,
+}
+
+/// Detect a terminal multiplexer from environment variables.
+/// Return None if not running in a multiplexer.
+pub fn detect_multiplexer() -> Option<Multiplexer> {
+    multiplexer_from_env(|k| env::var(k).ok())
+}
+
+/// Detect a terminal multiplexer from the secondary device attributes (DA2).
+/// This works even if the environment is lost, e.g. via sudo or ssh in a multiplexer.
+/// DA1 is sent after DA2, so None is returned instead of blocking
+/// when the terminal doesn't answer DA2.
+pub fn detect_multiplexer_by_da<W: io::Write, R: ReadReply>(
+    w: &mut W,
+    r: &mut R,
+) -> Option<Multiplexer> {
+    query(w, r, multiplexer_da_reply)
+}
+
+/// Check whether the terminal is connected over SSH.
+/// Components may pick conservative defaults, e.g. no images or lower frame rate, on remote connections.
+pub fn is_remote() -> bool {
+    remote_from_env(|k| env::var(k).ok())
+}
+
+fn remote_from_env<F: Fn(&str) -> Option<String>>(var: F) -> bool {
+    ["SSH_TTY", "SSH_CONNECTION", "SSH_CLIENT"]
+        .iter()
+        .any(|k| var(k).is_some_and(|v| !v.is_empty()))
+}
+
+fn multiplexer_from_env<F: Fn(&str) -> Option<String>>(var: F) -> Option<Multiplexer> {
+    if var("TMUX").is_some() {
+        return Some(Multiplexer::Tmux);
+    }
+    if var("ZELLIJ").is_some() {
+        return Some(Multiplexer::Zellij);
+    }
+    if var("STY").is_some() {
+        return Some(Multiplexer::Screen);
+    }
+    match var("TERM") {
+        Some(ref t) if t.starts_with("tmux") => Some(Multiplexer::Tmux),
+        Some(ref t) if t.starts_with("screen") => Some(Multiplexer::Screen),
+        _ => None,
+    }
+}
+
+fn multiplexer_da_reply<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<Multiplexer> {
+    match da2_reply(w, r)?.terminal {
+        // 'T'
+        84 => Some(Multiplexer::Tmux),
+        // 'S'
+        83 => Some(Multiplexer::Screen),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(vs: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |k| vs.iter().find(|v| v.0 == k).map(|v| v.1.to_string())
+    }
+
+    #[test]
+    fn test_multiplexer_from_env() {
+        let env = vars(&[("TMUX", "/tmp/tmux-1000/default,1,0"), ("TERM", "screen")]);
+        assert_eq!(multiplexer_from_env(env), Some(Multiplexer::Tmux));
+        let env = vars(&[("ZELLIJ", "0")]);
+        assert_eq!(multiplexer_from_env(env), Some(Multiplexer::Zellij));
+        let env = vars(&[("STY", "1234.pts-0.host")]);
+        assert_eq!(multiplexer_from_env(env), Some(Multiplexer::Screen));
+        let env = vars(&[("TERM", "tmux-256color")]);
+        assert_eq!(multiplexer_from_env(env), Some(Multiplexer::Tmux));
+        let env = vars(&[("TERM", "xterm-256color")]);
+        assert_eq!(multiplexer_from_env(env), None);
+    }
+
+    #[test]
+    fn test_multiplexer_da_reply() {
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1b[>84;0;0c\x1b[?62c";
+        assert_eq!(
+            multiplexer_da_reply(&mut w, &mut r),
+            Some(Multiplexer::Tmux)
+        );
+        assert_eq!(w, b"\x1b[>c\x1b[c");
+        let mut r: &[u8] = b"\x1b[>83;40800;0c\x1b[?62c";
+        assert_eq!(
+            multiplexer_da_reply(&mut w, &mut r),
+            Some(Multiplexer::Screen)
+        );
+        let mut r: &[u8] = b"\x1b[>41;380;0c\x1b[?62c";
+        assert_eq!(multiplexer_da_reply(&mut w, &mut r), None);
+        let mut r: &[u8] = b"\x1b[?62c";
+        assert_eq!(multiplexer_da_reply(&mut w, &mut r), None);
+    }
+
+    #[test]
+    fn test_remote_from_env() {
+        assert!(remote_from_env(vars(&[("SSH_TTY", "/dev/pts/3")])));
+        assert!(remote_from_env(vars(&[(
+            "SSH_CONNECTION",
+            "10.0.0.1 51234 10.0.0.2 22"
+        )])));
+        assert!(!remote_from_env(vars(&[("SSH_TTY", "")])));
+        assert!(!remote_from_env(vars(&[("TERM", "xterm")])));
+    }
+}
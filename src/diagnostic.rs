@@ -0,0 +1,156 @@
+// Compiler style diagnostics with source excerpts and hyperlinks
+
+use crate::csi::{self, SgrCode};
+use crate::hyperlink;
+use crate::text::display_width;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+
+    fn color(self) -> SgrCode {
+        match self {
+            Severity::Error => SgrCode::FgColorBrightRed,
+            Severity::Warning => SgrCode::FgColorBrightYellow,
+            Severity::Note => SgrCode::FgColorBrightCyan,
+        }
+    }
+}
+
+/// Location in a source file, with the text of its line for the excerpt.
+pub struct Span<'a> {
+    pub path: &'a Path,
+    /// 1 based line number
+    pub line: usize,
+    /// 1 based column in characters
+    pub col: usize,
+    /// Number of characters underlined, at least one caret is drawn
+    pub len: usize,
+    pub source_line: &'a str,
+    pub label: Option<&'a str>,
+}
+
+pub struct Diagnostic<'a> {
+    pub severity: Severity,
+    pub message: &'a str,
+    pub span: Option<Span<'a>>,
+}
+
+impl<'a> Diagnostic<'a> {
+    pub fn new(severity: Severity, message: &'a str) -> Diagnostic<'a> {
+        Diagnostic {
+            severity,
+            message,
+            span: None,
+        }
+    }
+}
+
+/// Write d in the style of rustc.
+/// With color, the severity and carets are colored and the location is an OSC 8 link to the file and line.
+pub fn format<W: io::Write>(w: &mut W, d: &Diagnostic, color: bool) -> io::Result<()> {
+    let style = |w: &mut W, codes: Vec<SgrCode>| -> io::Result<()> {
+        if color {
+            for c in codes {
+                csi::sgr(w, c)?;
+            }
+        }
+        Ok(())
+    };
+    style(w, vec![SgrCode::Bold, d.severity.color()])?;
+    write!(w, "{}", d.severity.name())?;
+    style(w, vec![SgrCode::Normal, SgrCode::Bold])?;
+    write!(w, ": {}", d.message)?;
+    style(w, vec![SgrCode::Normal])?;
+    writeln!(w)?;
+    let span = match d.span {
+        Some(ref s) => s,
+        None => return Ok(()),
+    };
+    let gutter = " ".repeat(span.line.to_string().len());
+    let location = format!("{}:{}:{}", span.path.display(), span.line, span.col);
+    write!(w, "{}--> ", gutter)?;
+    match hyperlink::file_line(span.path, span.line) {
+        Ok(uri) if color => hyperlink::link(w, &uri, &location)?,
+        _ => write!(w, "{}", location)?,
+    }
+    writeln!(w)?;
+    writeln!(w, "{} |", gutter)?;
+    writeln!(w, "{} | {}", span.line, span.source_line)?;
+    // carets are placed by display width, so wide characters before or in the span are accounted for
+    let start = span.source_line.chars().take(span.col.saturating_sub(1));
+    let underlined = span
+        .source_line
+        .chars()
+        .skip(span.col.saturating_sub(1))
+        .take(span.len);
+    let indent = display_width(&start.collect::<String>());
+    let carets = display_width(&underlined.collect::<String>()).max(1);
+    write!(w, "{} | {}", gutter, " ".repeat(indent))?;
+    style(w, vec![SgrCode::Bold, d.severity.color()])?;
+    write!(w, "{}", "^".repeat(carets))?;
+    if let Some(label) = span.label {
+        write!(w, " {}", label)?;
+    }
+    style(w, vec![SgrCode::Normal])?;
+    writeln!(w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic() -> Diagnostic<'static> {
+        Diagnostic {
+            span: Some(Span {
+                path: Path::new("src/main.rs"),
+                line: 12,
+                col: 10,
+                len: 3,
+                source_line: "let 名前 = foo;",
+                label: Some("not found"),
+            }),
+            ..Diagnostic::new(Severity::Error, "cannot find value `foo`")
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let mut w = Vec::new();
+        format(&mut w, &diagnostic(), false).unwrap();
+        assert_eq!(
+            String::from_utf8(w).unwrap(),
+            "error: cannot find value `foo`\n  \
+             --> src/main.rs:12:10\n   |\n\
+             12 | let 名前 = foo;\n   |            ^^^ not found\n"
+        );
+        let mut w = Vec::new();
+        format(&mut w, &Diagnostic::new(Severity::Note, "done"), false).unwrap();
+        assert_eq!(w, b"note: done\n");
+    }
+
+    #[test]
+    fn test_format_color() {
+        let mut w = Vec::new();
+        format(&mut w, &diagnostic(), true).unwrap();
+        let s = String::from_utf8(w).unwrap();
+        assert!(s.starts_with("\x1b[1m\x1b[91merror\x1b[0m\x1b[1m: cannot find"));
+        assert!(s.contains("\x1b]8;;file://"));
+        assert!(s.contains("#line=12\x1b\\src/main.rs:12:10\x1b]8;;\x1b\\"));
+        assert!(s.ends_with("\x1b[1m\x1b[91m^^^ not found\x1b[0m\n"));
+    }
+}
@@ -0,0 +1,47 @@
+// Escape sequences which are not introduced by CSI
+
+use std::io;
+
+#[macro_export]
+macro_rules! esc {
+    ($( $s:expr ),*) => { concat!("\x1b", $( $s ),*) };
+}
+
+/// DECSC: save cursor
+/// Cursor position, SGR attributes and character set are saved.
+pub fn decsc<W: io::Write>(w: &mut W) -> io::Result<()> {
+    w.write_fmt(format_args!(esc!("7")))?;
+    Ok(())
+}
+
+/// DECRC: restore cursor
+pub fn decrc<W: io::Write>(w: &mut W) -> io::Result<()> {
+    w.write_fmt(format_args!(esc!("8")))?;
+    Ok(())
+}
+
+/// HTS: horizontal tab set
+/// Set a tab stop at the cursor column.
+pub fn hts<W: io::Write>(w: &mut W) -> io::Result<()> {
+    w.write_fmt(format_args!(esc!("H")))?;
+    Ok(())
+}
+
+/// RIS: reset to initial state
+/// Full reset of the terminal, which also clears the screen.
+pub fn ris<W: io::Write>(w: &mut W) -> io::Result<()> {
+    w.write_fmt(format_args!(esc!("c")))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ris() {
+        let mut w = Vec::new();
+        ris(&mut w).unwrap();
+        assert_eq!(w, b"\x1bc");
+    }
+}
@@ -0,0 +1,89 @@
+// Hyperlinks (OSC 8) and URI helpers for them
+
+use std::ffi::CStr;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// Write text as a hyperlink to uri.
+pub fn link<W: io::Write>(w: &mut W, uri: &str, text: &str) -> io::Result<()> {
+    w.write_fmt(format_args!(osc!("8;;{}"), uri))?;
+    w.write_all(text.as_bytes())?;
+    w.write_fmt(format_args!(osc!("8;;")))?;
+    Ok(())
+}
+
+/// Build `file://hostname/path` URI.
+/// Relative path is resolved against the current directory.
+pub fn file(path: &Path) -> io::Result<String> {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+    let mut uri = format!("file://{}", percent_encode(hostname()?.as_bytes()));
+    uri.push_str(&percent_encode(path.as_os_str().as_bytes()));
+    Ok(uri)
+}
+
+/// Build `file://` URI with `#line=N` fragment.
+pub fn file_line(path: &Path, line: usize) -> io::Result<String> {
+    Ok(format!("{}#line={}", file(path)?, line))
+}
+
+fn hostname() -> io::Result<String> {
+    let mut buf = [0u8; 256];
+    let ret = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // gethostname may not terminate a truncated name
+    buf[buf.len() - 1] = 0;
+    let name = unsafe { CStr::from_ptr(buf.as_ptr() as *const libc::c_char) };
+    Ok(name.to_string_lossy().into_owned())
+}
+
+// keep unreserved characters and '/'
+fn percent_encode(s: &[u8]) -> String {
+    let mut out = String::with_capacity(s.len());
+    for &b in s {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link() {
+        let mut w = Vec::new();
+        link(&mut w, "https://example.com", "example").unwrap();
+        assert_eq!(
+            w,
+            &b"\x1b]8;;https://example.com\x1b\\example\x1b]8;;\x1b\\"[..]
+        );
+    }
+
+    #[test]
+    fn test_file() {
+        let host = percent_encode(hostname().unwrap().as_bytes());
+        assert_eq!(
+            file(Path::new("/tmp/a b%.rs")).unwrap(),
+            format!("file://{}/tmp/a%20b%25.rs", host)
+        );
+        assert_eq!(
+            file_line(Path::new("/src/lib.rs"), 12).unwrap(),
+            format!("file://{}/src/lib.rs#line=12", host)
+        );
+        assert!(file(Path::new("src/lib.rs"))
+            .unwrap()
+            .ends_with("/src/lib.rs"));
+    }
+}
@@ -0,0 +1,720 @@
+// Input decoding of key sequences read from the terminal
+
+use crate::csi;
+use std::ops::{BitOr, BitOrAssign};
+use std::str;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCode {
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Esc,
+    Up,
+    Down,
+    Right,
+    Left,
+    Home,
+    End,
+    Insert,
+    Delete,
+    PageUp,
+    PageDown,
+    F(u8),
+}
+
+/// Modifier keys held while a key was pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers(0);
+    pub const SHIFT: Modifiers = Modifiers(1);
+    pub const ALT: Modifiers = Modifiers(2);
+    pub const CTRL: Modifiers = Modifiers(4);
+
+    pub fn contains(self, other: Modifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    // xterm style modifier parameter: 1 + (shift | alt << 1 | ctrl << 2 | meta << 3)
+    // meta is folded into alt.
+    fn from_param(n: usize) -> Modifiers {
+        let bits = n.saturating_sub(1);
+        let mut m = Modifiers((bits & 0x7) as u8);
+        if bits & 0x8 != 0 {
+            m |= Modifiers::ALT;
+        }
+        m
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Modifiers) -> Modifiers {
+        Modifiers(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Modifiers {
+    fn bitor_assign(&mut self, rhs: Modifiers) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// A key press with its encoding details stripped.
+/// Ctrl-A is reported as `Char('a')` with `CTRL`, ESC-prefixed keys get `ALT`,
+/// and `CSI 1;5A` becomes `Up` with `CTRL`.
+/// Shifted printable characters are reported as-is (`Char('A')`) without `SHIFT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+impl KeyEvent {
+    pub fn new(code: KeyCode, modifiers: Modifiers) -> KeyEvent {
+        KeyEvent { code, modifiers }
+    }
+}
+
+impl From<KeyCode> for KeyEvent {
+    fn from(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, Modifiers::NONE)
+    }
+}
+
+/// Parse one key from the head of buf.
+/// Return the key and the number of bytes consumed.
+/// A lone ESC is taken as the Esc key, so buf is expected to hold whole reads.
+/// Return None if buf is empty, ends in the middle of a sequence or starts with an unknown one.
+pub fn parse_key(buf: &[u8]) -> Option<(KeyEvent, usize)> {
+    match *buf.first()? {
+        0x1b => parse_esc(buf),
+        b => {
+            if let Some(ev) = parse_c0(b) {
+                return Some((ev, 1));
+            }
+            parse_char(buf).map(|(c, n)| (KeyCode::Char(c).into(), n))
+        }
+    }
+}
+
+fn parse_c0(b: u8) -> Option<KeyEvent> {
+    let ev = match b {
+        b'\r' | b'\n' => KeyCode::Enter.into(),
+        b'\t' => KeyCode::Tab.into(),
+        0x08 | 0x7f => KeyCode::Backspace.into(),
+        0x00 => KeyEvent::new(KeyCode::Char(' '), Modifiers::CTRL),
+        0x01..=0x1a => KeyEvent::new(KeyCode::Char((b - 1 + b'a') as char), Modifiers::CTRL),
+        0x1c..=0x1f => KeyEvent::new(KeyCode::Char((b - 0x1c + b'\\') as char), Modifiers::CTRL),
+        _ => return None,
+    };
+    Some(ev)
+}
+
+fn parse_char(buf: &[u8]) -> Option<(char, usize)> {
+    let len = match buf[0] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return None,
+    };
+    let s = str::from_utf8(buf.get(..len)?).ok()?;
+    s.chars().next().map(|c| (c, len))
+}
+
+fn parse_esc(buf: &[u8]) -> Option<(KeyEvent, usize)> {
+    match buf.get(1) {
+        None => Some((KeyCode::Esc.into(), 1)),
+        Some(b'[') if buf.len() > 2 => parse_csi(buf),
+        Some(b'O') if buf.len() > 2 => parse_ss3(buf),
+        // only one ESC is an Alt prefix, ESC ESC [A is Alt + Up but a longer run is a series of Esc keys
+        Some(0x1b) if !matches!(buf.get(2), Some(b'[') | Some(b'O')) => {
+            Some((KeyCode::Esc.into(), 1))
+        }
+        Some(_) => {
+            // ESC prefix: Alt + key
+            let (mut ev, n) = parse_key(&buf[1..])?;
+            ev.modifiers |= Modifiers::ALT;
+            Some((ev, n + 1))
+        }
+    }
+}
+
+// ESC O <final>
+fn parse_ss3(buf: &[u8]) -> Option<(KeyEvent, usize)> {
+    let code = match buf[2] {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'P' => KeyCode::F(1),
+        b'Q' => KeyCode::F(2),
+        b'R' => KeyCode::F(3),
+        b'S' => KeyCode::F(4),
+        _ => return None,
+    };
+    Some((code.into(), 3))
+}
+
+// ESC [ <params> <final>
+fn parse_csi(buf: &[u8]) -> Option<(KeyEvent, usize)> {
+    let end = 2 + buf[2..].iter().position(|b| (0x40..=0x7e).contains(b))?;
+    let param = |i: usize| {
+        csi::params(&buf[2..end])
+            .nth(i)
+            .map_or(0, |p| p.value_or(0))
+    };
+    let modifiers = Modifiers::from_param(param(1));
+    let code = match buf[end] {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'P' => KeyCode::F(1),
+        b'Q' => KeyCode::F(2),
+        b'R' => KeyCode::F(3),
+        b'S' => KeyCode::F(4),
+        b'Z' => return Some((KeyEvent::new(KeyCode::Tab, Modifiers::SHIFT), end + 1)),
+        b'~' => match param(0) {
+            1 | 7 => KeyCode::Home,
+            2 => KeyCode::Insert,
+            3 => KeyCode::Delete,
+            4 | 8 => KeyCode::End,
+            5 => KeyCode::PageUp,
+            6 => KeyCode::PageDown,
+            n @ 11..=15 => KeyCode::F((n - 10) as u8),
+            n @ 17..=21 => KeyCode::F((n - 11) as u8),
+            n @ 23..=24 => KeyCode::F((n - 12) as u8),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some((KeyEvent::new(code, modifiers), end + 1))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wheel {
+    Up,
+    Down,
+}
+
+/// Parse a mouse wheel report from the head of buf.
+/// Both the legacy (`CSI M Cb Cx Cy`) and the SGR (`CSI < Pb;Px;Py M`) encodings are accepted.
+/// Return None if buf doesn't begin with a complete wheel report.
+pub fn parse_wheel(buf: &[u8]) -> Option<(Wheel, usize)> {
+    let (button, n) = match buf.get(..3)? {
+        b"\x1b[M" => (usize::from(*buf.get(3)?).checked_sub(32)?, 6),
+        b"\x1b[<" => {
+            let end = 3 + buf[3..].iter().position(|&b| b == b'M' || b == b'm')?;
+            let pb = str::from_utf8(&buf[3..end]).ok()?.split(';').next()?;
+            (pb.parse().ok()?, end + 1)
+        }
+        _ => return None,
+    };
+    if buf.len() < n {
+        return None;
+    }
+    // ignore shift, meta and ctrl bits
+    match button & !0x1c {
+        64 => Some((Wheel::Up, n)),
+        65 => Some((Wheel::Down, n)),
+        _ => None,
+    }
+}
+
+/// DEC locator report (DECLRP), sent after DECELR has enabled the locator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocatorReport {
+    /// 0: locator unavailable, 1: requested by DECRQLP, 2..=9: button down/up pairs
+    /// for left, middle, right and button 4, 10: left the filter rectangle
+    pub event: usize,
+    /// Buttons held, 1: right, 2: middle, 4: left, 8: button 4
+    pub buttons: usize,
+    pub row: usize,
+    pub col: usize,
+    pub page: usize,
+}
+
+/// Parse a DECLRP report `CSI Pe;Pb;Pr;Pc;Pp & w` from the head of buf.
+pub fn parse_locator(buf: &[u8]) -> Option<(LocatorReport, usize)> {
+    let body = buf.strip_prefix(b"\x1b[")?;
+    let end = body.iter().position(|b| !matches!(b, b'0'..=b'9' | b';'))?;
+    if body.get(end..end + 2)? != b"&w" {
+        return None;
+    }
+    let mut ps = csi::params(&body[..end]).map(|p| p.value_or(0));
+    let mut next = || ps.next().unwrap_or(0);
+    let report = LocatorReport {
+        event: next(),
+        buttons: next(),
+        row: next(),
+        col: next(),
+        page: next(),
+    };
+    Some((report, 2 + end + 2))
+}
+
+/// An input event decoded by parse_event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Key(KeyEvent),
+    Wheel(Wheel),
+    Locator(LocatorReport),
+    /// Focus reporting: true when the terminal gains focus, false when it loses it.
+    Focus(bool),
+    /// A sequence or byte that is not understood, kept whole so that the rest of the input stays in sync.
+    Unknown(Vec<u8>),
+    /// Undecoded bytes of a key, or the contents of a bracketed paste, delivered by Decoder in literal-next mode.
+    Raw(Vec<u8>),
+}
+
+/// Parse one event from the head of buf.
+/// Return the event and the number of bytes consumed, which is at least one.
+/// Return None only if buf is empty or ends in the middle of a sequence, so arbitrary input never stalls.
+pub fn parse_event(buf: &[u8]) -> Option<(Event, usize)> {
+    if let Some((wheel, n)) = parse_wheel(buf) {
+        return Some((Event::Wheel(wheel), n));
+    }
+    match buf.get(..3) {
+        Some(b"\x1b[I") => return Some((Event::Focus(true), 3)),
+        Some(b"\x1b[O") => return Some((Event::Focus(false), 3)),
+        _ => {}
+    }
+    if let Some((report, n)) = parse_locator(buf) {
+        return Some((Event::Locator(report), n));
+    }
+    if let Some((key, n)) = parse_key(buf) {
+        return Some((Event::Key(key), n));
+    }
+    let n = unknown_len(buf)?;
+    Some((Event::Unknown(buf[..n].to_vec()), n))
+}
+
+// length of the unknown sequence at the head of buf, None if it is incomplete
+fn unknown_len(buf: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    while buf[i..].starts_with(b"\x1b") {
+        match buf.get(i + 1)? {
+            // legacy mouse report: CSI M Cb Cx Cy
+            b'[' if buf.get(i + 2) == Some(&b'M') => {
+                if buf.len() < i + 6 {
+                    return None;
+                } else {
+                    return Some(i + 6);
+                }
+            }
+            b'[' => {
+                for (j, &b) in buf.iter().enumerate().skip(i + 2) {
+                    match b {
+                        0x20..=0x3f => {}
+                        0x40..=0x7e => return Some(j + 1),
+                        // malformed, cut before the stray byte
+                        _ => return Some(j),
+                    }
+                }
+                return None;
+            }
+            b'O' => return Some(i + 3),
+            // one ESC prefix as in parse_esc, the first ESC of a run is taken alone
+            _ if i == 0 => i = 1,
+            _ => return Some(i),
+        }
+    }
+    let b = *buf.get(i)?;
+    let len = match b {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    };
+    let continued = buf[i + 1..]
+        .iter()
+        .take(len - 1)
+        .all(|b| (0x80..=0xbf).contains(b));
+    if buf.len() < i + len && continued {
+        None
+    } else {
+        Some(i + 1)
+    }
+}
+
+/// Event decoder with a literal-next mode, as Ctrl-V in editors.
+#[derive(Debug, Clone, Default)]
+pub struct Decoder {
+    literal: bool,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
+
+    /// Deliver the next key as Event::Raw without decoding it.
+    /// A bracketed paste that comes next is delivered whole, without its markers.
+    pub fn literal_next(&mut self) {
+        self.literal = true;
+    }
+
+    pub fn is_literal(&self) -> bool {
+        self.literal
+    }
+
+    /// Parse one event from the head of buf as parse_event does, honoring literal-next mode.
+    pub fn decode(&mut self, buf: &[u8]) -> Option<(Event, usize)> {
+        if !self.literal {
+            return parse_event(buf);
+        }
+        const PASTE_START: &[u8] = b"\x1b[200~";
+        const PASTE_END: &[u8] = b"\x1b[201~";
+        let (ev, n) = if let Some(body) = buf.strip_prefix(PASTE_START) {
+            let end = body.windows(PASTE_END.len()).position(|s| s == PASTE_END)?;
+            (
+                Event::Raw(body[..end].to_vec()),
+                PASTE_START.len() + end + PASTE_END.len(),
+            )
+        } else if buf.len() > 2 && buf.len() < PASTE_START.len() && PASTE_START.starts_with(buf) {
+            // maybe the start of a paste, a lone ESC or ESC [ is a key as in parse_key
+            return None;
+        } else {
+            let (_, n) = parse_event(buf)?;
+            (Event::Raw(buf[..n].to_vec()), n)
+        };
+        self.literal = false;
+        Some((ev, n))
+    }
+}
+
+/// What a wheel notch is translated into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WheelAction {
+    /// Scroll up the screen by n lines with SU.
+    ScrollUp(usize),
+    /// Scroll down the screen by n lines with SD.
+    ScrollDown(usize),
+    /// Behave as if the key was pressed n times.
+    Key(KeyEvent, usize),
+}
+
+/// Opt-in translation of wheel events as native pagers do.
+/// On the alternate screen the wheel acts as Up/Down keys,
+/// otherwise it scrolls the screen contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WheelTranslator {
+    pub alt_screen: bool,
+    pub lines: usize,
+}
+
+impl WheelTranslator {
+    pub fn new(alt_screen: bool, lines: usize) -> WheelTranslator {
+        WheelTranslator { alt_screen, lines }
+    }
+
+    pub fn translate(&self, wheel: Wheel) -> WheelAction {
+        match (self.alt_screen, wheel) {
+            (true, Wheel::Up) => WheelAction::Key(KeyCode::Up.into(), self.lines),
+            (true, Wheel::Down) => WheelAction::Key(KeyCode::Down.into(), self.lines),
+            // wheel up shows earlier contents, so the contents move down
+            (false, Wheel::Up) => WheelAction::ScrollDown(self.lines),
+            (false, Wheel::Down) => WheelAction::ScrollUp(self.lines),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: Modifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_parse_key_c0() {
+        assert_eq!(
+            parse_key(b"\x01"),
+            Some((key(KeyCode::Char('a'), Modifiers::CTRL), 1))
+        );
+        assert_eq!(
+            parse_key(b"\x1a"),
+            Some((key(KeyCode::Char('z'), Modifiers::CTRL), 1))
+        );
+        assert_eq!(
+            parse_key(b"\x1f"),
+            Some((key(KeyCode::Char('_'), Modifiers::CTRL), 1))
+        );
+        assert_eq!(
+            parse_key(b"\x00"),
+            Some((key(KeyCode::Char(' '), Modifiers::CTRL), 1))
+        );
+        assert_eq!(parse_key(b"\r"), Some((KeyCode::Enter.into(), 1)));
+        assert_eq!(parse_key(b"\n"), Some((KeyCode::Enter.into(), 1)));
+        assert_eq!(parse_key(b"\t"), Some((KeyCode::Tab.into(), 1)));
+        assert_eq!(parse_key(b"\x7f"), Some((KeyCode::Backspace.into(), 1)));
+    }
+
+    #[test]
+    fn test_parse_key_char() {
+        assert_eq!(parse_key(b"q"), Some((KeyCode::Char('q').into(), 1)));
+        assert_eq!(parse_key(b"Qx"), Some((KeyCode::Char('Q').into(), 1)));
+        assert_eq!(
+            parse_key("あ".as_bytes()),
+            Some((KeyCode::Char('あ').into(), 3))
+        );
+        assert_eq!(parse_key(&"あ".as_bytes()[..2]), None);
+        assert_eq!(parse_key(b""), None);
+    }
+
+    #[test]
+    fn test_parse_key_alt() {
+        assert_eq!(parse_key(b"\x1b"), Some((KeyCode::Esc.into(), 1)));
+        assert_eq!(
+            parse_key(b"\x1bx"),
+            Some((key(KeyCode::Char('x'), Modifiers::ALT), 2))
+        );
+        assert_eq!(
+            parse_key(b"\x1b\x01"),
+            Some((key(KeyCode::Char('a'), Modifiers::ALT | Modifiers::CTRL), 2))
+        );
+        assert_eq!(
+            parse_key(b"\x1b["),
+            Some((key(KeyCode::Char('['), Modifiers::ALT), 2))
+        );
+        assert_eq!(
+            parse_key(b"\x1b\x1b[A"),
+            Some((key(KeyCode::Up, Modifiers::ALT), 4))
+        );
+        assert_eq!(parse_key(b"\x1b\x1b\x1bx"), Some((KeyCode::Esc.into(), 1)));
+    }
+
+    #[test]
+    fn test_parse_key_csi() {
+        assert_eq!(parse_key(b"\x1b[A"), Some((KeyCode::Up.into(), 3)));
+        assert_eq!(parse_key(b"\x1bOD"), Some((KeyCode::Left.into(), 3)));
+        assert_eq!(
+            parse_key(b"\x1b[1;5A"),
+            Some((key(KeyCode::Up, Modifiers::CTRL), 6))
+        );
+        assert_eq!(
+            parse_key(b"\x1b[1;4C"),
+            Some((key(KeyCode::Right, Modifiers::SHIFT | Modifiers::ALT), 6))
+        );
+        assert_eq!(parse_key(b"\x1b[3~"), Some((KeyCode::Delete.into(), 4)));
+        assert_eq!(
+            parse_key(b"\x1b[5;5~"),
+            Some((key(KeyCode::PageUp, Modifiers::CTRL), 6))
+        );
+        assert_eq!(parse_key(b"\x1b[15~"), Some((KeyCode::F(5).into(), 5)));
+        assert_eq!(
+            parse_key(b"\x1b[24;2~"),
+            Some((key(KeyCode::F(12), Modifiers::SHIFT), 7))
+        );
+        assert_eq!(
+            parse_key(b"\x1b[1;3P"),
+            Some((key(KeyCode::F(1), Modifiers::ALT), 6))
+        );
+        assert_eq!(
+            parse_key(b"\x1b[Z"),
+            Some((key(KeyCode::Tab, Modifiers::SHIFT), 3))
+        );
+        assert_eq!(parse_key(b"\x1b[1;5"), None);
+        assert_eq!(parse_key(b"\x1b[99~"), None);
+    }
+
+    #[test]
+    fn test_parse_wheel() {
+        assert_eq!(parse_wheel(b"\x1b[<64;10;5M"), Some((Wheel::Up, 11)));
+        assert_eq!(parse_wheel(b"\x1b[<69;1;1M"), Some((Wheel::Down, 10)));
+        assert_eq!(parse_wheel(b"\x1b[M`!!"), Some((Wheel::Up, 6)));
+        assert_eq!(parse_wheel(b"\x1b[Ma!!"), Some((Wheel::Down, 6)));
+        assert_eq!(parse_wheel(b"\x1b[<0;10;5M"), None);
+        assert_eq!(parse_wheel(b"\x1b[Ma!"), None);
+        assert_eq!(parse_wheel(b"\x1b[A"), None);
+    }
+
+    #[test]
+    fn test_wheel_translator() {
+        let t = WheelTranslator::new(false, 3);
+        assert_eq!(t.translate(Wheel::Up), WheelAction::ScrollDown(3));
+        assert_eq!(t.translate(Wheel::Down), WheelAction::ScrollUp(3));
+        let t = WheelTranslator::new(true, 1);
+        assert_eq!(
+            t.translate(Wheel::Up),
+            WheelAction::Key(KeyCode::Up.into(), 1)
+        );
+        assert_eq!(
+            t.translate(Wheel::Down),
+            WheelAction::Key(KeyCode::Down.into(), 1)
+        );
+    }
+
+    #[test]
+    fn test_parse_event() {
+        assert_eq!(
+            parse_event(b"\x1b[A"),
+            Some((Event::Key(KeyCode::Up.into()), 3))
+        );
+        assert_eq!(
+            parse_event(b"\x1b[<65;1;1M"),
+            Some((Event::Wheel(Wheel::Down), 10))
+        );
+        assert_eq!(
+            parse_event(b"\x1b[99~x"),
+            Some((Event::Unknown(b"\x1b[99~".to_vec()), 5))
+        );
+        assert_eq!(
+            parse_event(b"\x1b[M !!x"),
+            Some((Event::Unknown(b"\x1b[M !!".to_vec()), 6))
+        );
+        assert_eq!(
+            parse_event(b"\x1bOz"),
+            Some((Event::Unknown(b"\x1bOz".to_vec()), 3))
+        );
+        assert_eq!(
+            parse_event(b"\x1b[1\x01"),
+            Some((Event::Unknown(b"\x1b[1".to_vec()), 3))
+        );
+        assert_eq!(
+            parse_event(b"\xffa"),
+            Some((Event::Unknown(b"\xff".to_vec()), 1))
+        );
+        assert_eq!(
+            parse_event(b"\xe3\x81a"),
+            Some((Event::Unknown(b"\xe3".to_vec()), 1))
+        );
+        assert_eq!(parse_event(b"\xe3\x81"), None);
+        assert_eq!(parse_event(b"\x1b[1;5"), None);
+        assert_eq!(parse_event(b"\x1b[M !"), None);
+        assert_eq!(parse_event(b""), None);
+    }
+
+    #[test]
+    fn test_parse_event_focus() {
+        assert_eq!(parse_event(b"\x1b[I"), Some((Event::Focus(true), 3)));
+        assert_eq!(parse_event(b"\x1b[Ox"), Some((Event::Focus(false), 3)));
+    }
+
+    #[test]
+    fn test_parse_event_truncated() {
+        let seqs: [&[u8]; 5] = [
+            b"\x1b[24;2~",
+            b"\x1b[<64;10;5M",
+            b"\x1b[M !!",
+            "あ".as_bytes(),
+            b"\x1b[?1;2c",
+        ];
+        for seq in seqs.iter() {
+            for end in 1..seq.len() {
+                if let Some((_, n)) = parse_event(&seq[..end]) {
+                    assert!(n >= 1 && n <= end);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_event_random() {
+        // xorshift, so that failures are reproducible
+        let mut x = 0x2545_f491u32;
+        let mut buf = Vec::new();
+        for _ in 0..4096 {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            // bias toward bytes that start and continue sequences
+            buf.push(match x % 4 {
+                0 => 0x1b,
+                1 => b"[O<;M~0123456789ABZ"[(x >> 8) as usize % 19],
+                _ => (x >> 16) as u8,
+            });
+        }
+        let mut rest = &buf[..];
+        while let Some((_, n)) = parse_event(rest) {
+            assert!(n >= 1 && n <= rest.len());
+            rest = &rest[n..];
+        }
+        // only an incomplete tail may be left
+        assert!(rest.is_empty() || rest[0] == 0x1b || rest[0] >= 0xc0);
+    }
+
+    #[test]
+    fn test_parse_event_esc_run() {
+        // a long run of ESC must neither overflow the stack nor fold into one key
+        let mut buf = vec![0x1b; 2_000_000];
+        buf.push(b'a');
+        assert_eq!(
+            parse_event(&buf),
+            Some((Event::Key(KeyCode::Esc.into()), 1))
+        );
+        let (ev, n) = parse_event(&buf[buf.len() - 2..]).unwrap();
+        assert_eq!(
+            (ev, n),
+            (Event::Key(key(KeyCode::Char('a'), Modifiers::ALT)), 2)
+        );
+        assert_eq!(unknown_len(&buf), Some(1));
+    }
+
+    #[test]
+    fn test_decoder_literal_next() {
+        let mut d = Decoder::new();
+        assert_eq!(
+            d.decode(b"\x1b[A"),
+            Some((Event::Key(KeyCode::Up.into()), 3))
+        );
+        d.literal_next();
+        assert_eq!(
+            d.decode(b"\x1b[Ax"),
+            Some((Event::Raw(b"\x1b[A".to_vec()), 3))
+        );
+        assert!(!d.is_literal());
+        d.literal_next();
+        assert_eq!(d.decode(b"\x1b"), Some((Event::Raw(b"\x1b".to_vec()), 1)));
+        d.literal_next();
+        assert_eq!(d.decode(b"\x1b["), Some((Event::Raw(b"\x1b[".to_vec()), 2)));
+        d.literal_next();
+        assert_eq!(d.decode(b"\x1b[20"), None);
+        assert_eq!(d.decode(b"\x1b[200~a\x1b[B"), None);
+        assert_eq!(
+            d.decode(b"\x1b[200~a\x1b[B\x1b[201~x"),
+            Some((Event::Raw(b"a\x1b[B".to_vec()), 16))
+        );
+        assert_eq!(
+            d.decode(b"\x01"),
+            Some((
+                Event::Key(KeyEvent::new(KeyCode::Char('a'), Modifiers::CTRL)),
+                1
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_locator() {
+        let report = LocatorReport {
+            event: 2,
+            buttons: 4,
+            row: 10,
+            col: 20,
+            page: 1,
+        };
+        assert_eq!(parse_locator(b"\x1b[2;4;10;20;1&w"), Some((report, 15)));
+        assert_eq!(
+            parse_event(b"\x1b[2;4;10;20;1&wx"),
+            Some((Event::Locator(report), 15))
+        );
+        assert_eq!(parse_locator(b"\x1b[0&w").map(|(r, _)| r.event), Some(0));
+        assert_eq!(parse_locator(b"\x1b[2;4;10"), None);
+        assert_eq!(parse_locator(b"\x1b[2;4A"), None);
+    }
+}
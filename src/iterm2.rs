@@ -0,0 +1,76 @@
+// iTerm2 proprietary sequences (OSC 1337)
+// https://iterm2.com/documentation-escape-codes.html
+
+use std::io;
+
+pub use crate::osc::set_user_var;
+
+/// Value of RequestAttention.
+pub enum Attention {
+    Yes,       // bounce the dock icon until the app is activated
+    No,        // cancel a previous request
+    Once,      // bounce the dock icon once
+    Fireworks, // show fireworks at the cursor
+}
+
+/// RequestAttention: ask for attention of the user.
+pub fn request_attention<W: io::Write>(w: &mut W, a: Attention) -> io::Result<()> {
+    let a = match a {
+        Attention::Yes => "yes",
+        Attention::No => "no",
+        Attention::Once => "once",
+        Attention::Fireworks => "fireworks",
+    };
+    w.write_fmt(format_args!(osc!("1337;RequestAttention={}"), a))?;
+    Ok(())
+}
+
+/// HighlightCursorLine: show or hide the cursor guide.
+/// The color of the guide is a profile setting and can't be changed by a sequence.
+pub fn cursor_guide<W: io::Write>(w: &mut W, show: bool) -> io::Result<()> {
+    let show = if show { "yes" } else { "no" };
+    w.write_fmt(format_args!(osc!("1337;HighlightCursorLine={}"), show))?;
+    Ok(())
+}
+
+/// ShellIntegrationVersion: report the version of the shell integration script.
+pub fn shell_integration_version<W: io::Write>(
+    w: &mut W,
+    version: usize,
+    shell: Option<&str>,
+) -> io::Result<()> {
+    match shell {
+        Some(shell) => w.write_fmt(format_args!(
+            osc!("1337;ShellIntegrationVersion={};shell={}"),
+            version, shell
+        ))?,
+        None => w.write_fmt(format_args!(
+            osc!("1337;ShellIntegrationVersion={}"),
+            version
+        ))?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iterm2() {
+        let mut w = Vec::new();
+        set_user_var(&mut w, "state", "foo").unwrap();
+        request_attention(&mut w, Attention::Once).unwrap();
+        cursor_guide(&mut w, true).unwrap();
+        shell_integration_version(&mut w, 13, Some("zsh")).unwrap();
+        shell_integration_version(&mut w, 13, None).unwrap();
+        assert_eq!(
+            String::from_utf8(w).unwrap(),
+            "\x1b]1337;SetUserVar=state=Zm9v\x1b\\\
+             \x1b]1337;RequestAttention=once\x1b\\\
+             \x1b]1337;HighlightCursorLine=yes\x1b\\\
+             \x1b]1337;ShellIntegrationVersion=13;shell=zsh\x1b\\\
+             \x1b]1337;ShellIntegrationVersion=13\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,361 @@
+// kitty terminal protocols
+// keyboard: https://sw.kovidgoyal.net/kitty/keyboard-protocol/
+// notification: https://sw.kovidgoyal.net/kitty/desktop-notifications/
+
+use crate::csi::{query, read_until_da1, reply_params, string_payload, ReadReply};
+use crate::{base64, osc};
+use std::convert::TryFrom;
+use std::io;
+use std::ops::{BitOr, Deref, DerefMut};
+
+/// Progressive enhancement flags of the kitty keyboard protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyboardFlags(u8);
+
+impl KeyboardFlags {
+    pub const DISAMBIGUATE_ESCAPE_CODES: KeyboardFlags = KeyboardFlags(1);
+    pub const REPORT_EVENT_TYPES: KeyboardFlags = KeyboardFlags(2);
+    pub const REPORT_ALTERNATE_KEYS: KeyboardFlags = KeyboardFlags(4);
+    pub const REPORT_ALL_KEYS_AS_ESCAPE_CODES: KeyboardFlags = KeyboardFlags(8);
+    pub const REPORT_ASSOCIATED_TEXT: KeyboardFlags = KeyboardFlags(16);
+
+    pub fn from_bits(bits: u8) -> KeyboardFlags {
+        KeyboardFlags(bits & 0x1f)
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(self, other: KeyboardFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for KeyboardFlags {
+    type Output = KeyboardFlags;
+
+    fn bitor(self, rhs: KeyboardFlags) -> KeyboardFlags {
+        KeyboardFlags(self.0 | rhs.0)
+    }
+}
+
+/// Push flags onto the keyboard mode stack.
+pub fn keyboard_push<W: io::Write>(w: &mut W, flags: KeyboardFlags) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!(">{}u"), flags.bits()))?;
+    Ok(())
+}
+
+/// Pop n entries from the keyboard mode stack.
+pub fn keyboard_pop<W: io::Write>(w: &mut W, n: usize) -> io::Result<()> {
+    w.write_fmt(format_args!(csi!("<{}u"), n))?;
+    Ok(())
+}
+
+/// Query current keyboard flags.
+/// The query is followed by DA1 so that None is returned instead of blocking
+/// when the terminal doesn't support the protocol.
+pub fn keyboard_query<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<KeyboardFlags> {
+    query(w, r, keyboard_query_reply)
+}
+
+// => "\x1b[?${flags}u" (if supported) and "\x1b[?${attrs}c"
+fn keyboard_query_reply<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<KeyboardFlags> {
+    w.write_fmt(format_args!(concat!(csi!("?u"), csi!("c"))))
+        .ok()?;
+    w.flush().ok()?;
+    let mut flags = None;
+    read_until_da1(r, |seq| {
+        if let Some(params) = reply_params(seq, b"\x1b[?", b'u') {
+            // a value out of range is a broken reply, not flags
+            flags = match params[..] {
+                [bits] => u8::try_from(bits).ok().map(KeyboardFlags::from_bits),
+                _ => None,
+            };
+        }
+    })?;
+    flags
+}
+
+/// Push keyboard flags on creation and pop them on drop.
+/// The guard dereferences to the wrapped writer.
+pub struct KeyboardEnhancementGuard<'a, W: io::Write> {
+    w: &'a mut W,
+}
+
+impl<'a, W: io::Write> KeyboardEnhancementGuard<'a, W> {
+    pub fn new(w: &'a mut W, flags: KeyboardFlags) -> io::Result<Self> {
+        keyboard_push(w, flags)?;
+        w.flush()?;
+        Ok(KeyboardEnhancementGuard { w })
+    }
+}
+
+impl<'a, W: io::Write> Deref for KeyboardEnhancementGuard<'a, W> {
+    type Target = W;
+
+    fn deref(&self) -> &W {
+        self.w
+    }
+}
+
+impl<'a, W: io::Write> DerefMut for KeyboardEnhancementGuard<'a, W> {
+    fn deref_mut(&mut self) -> &mut W {
+        self.w
+    }
+}
+
+impl<'a, W: io::Write> Drop for KeyboardEnhancementGuard<'a, W> {
+    fn drop(&mut self) {
+        let _ = keyboard_pop(self.w, 1).and_then(|_| self.w.flush());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    Low = 0,
+    Normal = 1,
+    Critical = 2,
+}
+
+/// Desktop notification of OSC 99.
+pub struct Notification<'a> {
+    /// Identifier to match reports and to update the notification.
+    /// It may contain only ASCII letters, digits and `_+.-`.
+    pub id: Option<&'a str>,
+    pub title: &'a str,
+    pub body: Option<&'a str>,
+    pub urgency: Option<Urgency>,
+    /// Focus the window when the notification is activated.
+    pub focus: bool,
+    /// Report activation with `OSC 99 ; i=id ; ST`.
+    pub report: bool,
+    /// Report closing with `OSC 99 ; i=id:p=close ; ST`.
+    pub report_close: bool,
+}
+
+impl<'a> Notification<'a> {
+    pub fn new(title: &'a str) -> Notification<'a> {
+        Notification {
+            id: None,
+            title,
+            body: None,
+            urgency: None,
+            focus: true,
+            report: false,
+            report_close: false,
+        }
+    }
+}
+
+/// Report of a notification sent back by the terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotificationReport {
+    Activated(String),
+    Closed(String),
+}
+
+/// Post a desktop notification (OSC 99).
+/// Title and body are base64 encoded, so they may contain any characters.
+/// Return an InvalidInput error if the id has other characters than kitty allows,
+/// as they could end the metadata or the sequence.
+pub fn notify<W: io::Write>(w: &mut W, n: &Notification) -> io::Result<()> {
+    if !n.id.is_none_or(valid_id) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "notification id may contain only [a-zA-Z0-9_+.-]",
+        ));
+    }
+    let mut meta = String::new();
+    if let Some(id) = n.id {
+        meta.push_str(&format!("i={}:", id));
+    }
+    let actions = match (n.focus, n.report) {
+        (true, true) => "focus,report",
+        (true, false) => "focus",
+        (false, true) => "-focus,report",
+        (false, false) => "-focus",
+    };
+    meta.push_str(&format!("a={}:", actions));
+    if n.report_close {
+        meta.push_str("c=1:");
+    }
+    if let Some(u) = n.urgency {
+        meta.push_str(&format!("u={}:", u as usize));
+    }
+    let done = if n.body.is_some() { 0 } else { 1 };
+    w.write_fmt(format_args!(
+        osc!("99;{}d={}:e=1:p=title;{}"),
+        meta,
+        done,
+        base64::encode(n.title.as_bytes())
+    ))?;
+    if let Some(body) = n.body {
+        let id = n.id.map(|id| format!("i={}:", id)).unwrap_or_default();
+        w.write_fmt(format_args!(
+            osc!("99;{}d=1:e=1:p=body;{}"),
+            id,
+            base64::encode(body.as_bytes())
+        ))?;
+    }
+    Ok(())
+}
+
+fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"_+.-".contains(&b))
+}
+
+/// Check whether the terminal supports desktop notifications of OSC 99.
+/// DA1 is sent after the query, so false is returned instead of blocking on other terminals.
+pub fn notify_supported<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> bool {
+    query(w, r, notify_supported_reply).unwrap_or(false)
+}
+
+// => "\x1b]99;i=${id}:p=?;${capabilities}\x1b\\" (if supported) and "\x1b[?${attrs}c"
+fn notify_supported_reply<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<bool> {
+    w.write_fmt(format_args!(concat!(osc!("99;i=ansi-csi:p=?;"), csi!("c"))))
+        .ok()?;
+    w.flush().ok()?;
+    let mut supported = false;
+    read_until_da1(r, |seq| {
+        if let Some(body) = string_payload(seq, b"\x1b]99;") {
+            let meta = body.split(|&b| b == b';').next().unwrap_or(b"");
+            supported |= meta.split(|&b| b == b':').any(|kv| kv == b"p=?");
+        }
+    })?;
+    Some(supported)
+}
+
+/// Post a notification with OSC 99 if the terminal supports it, otherwise with OSC 9.
+/// The terminal is asked by notify_supported each time; to ask once,
+/// keep its result and call notify or `osc::notify` instead.
+pub fn notify_with_fallback<W: io::Write, R: ReadReply>(
+    w: &mut W,
+    r: &mut R,
+    n: &Notification,
+) -> io::Result<()> {
+    let osc99 = notify_supported(w, r);
+    notify_either(w, n, osc99)
+}
+
+fn notify_either<W: io::Write>(w: &mut W, n: &Notification, osc99: bool) -> io::Result<()> {
+    if osc99 {
+        return notify(w, n);
+    }
+    let message = match n.body {
+        Some(body) => format!("{}: {}", n.title, body),
+        None => n.title.to_string(),
+    };
+    osc::notify(w, &message)
+}
+
+/// Parse an activation or close report from seq.
+pub fn parse_notification_report(seq: &[u8]) -> Option<NotificationReport> {
+    let body = string_payload(seq, b"\x1b]99;")?;
+    let meta = body.split(|&b| b == b';').next()?;
+    let (mut id, mut close) = (None, false);
+    for kv in meta.split(|&b| b == b':') {
+        match kv {
+            [b'i', b'=', v @ ..] => id = Some(String::from_utf8_lossy(v).into_owned()),
+            b"p=close" => close = true,
+            _ => {}
+        }
+    }
+    let id = id?;
+    Some(if close {
+        NotificationReport::Closed(id)
+    } else {
+        NotificationReport::Activated(id)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyboard_enhancement_guard() {
+        let mut w = Vec::new();
+        {
+            let flags =
+                KeyboardFlags::DISAMBIGUATE_ESCAPE_CODES | KeyboardFlags::REPORT_EVENT_TYPES;
+            let mut g = KeyboardEnhancementGuard::new(&mut w, flags).unwrap();
+            g.push(b'x');
+        }
+        assert_eq!(w, b"\x1b[>3ux\x1b[<1u");
+    }
+
+    #[test]
+    fn test_keyboard_query() {
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1b[?5u\x1b[?62;22c";
+        assert_eq!(
+            keyboard_query_reply(&mut w, &mut r),
+            Some(KeyboardFlags::from_bits(5))
+        );
+        assert_eq!(w, b"\x1b[?u\x1b[c");
+        let mut r: &[u8] = b"\x1b[?62;22c";
+        assert_eq!(keyboard_query_reply(&mut w, &mut r), None);
+        let mut r: &[u8] = b"\x1b[?300u\x1b[?62;22c";
+        assert_eq!(keyboard_query_reply(&mut w, &mut r), None);
+        // a 'c' in another reply doesn't end the query
+        let mut r: &[u8] = b"\x1b]l\x63\x1b\\\x1b[?1u\x1b[?62c";
+        assert_eq!(
+            keyboard_query_reply(&mut w, &mut r),
+            Some(KeyboardFlags::DISAMBIGUATE_ESCAPE_CODES)
+        );
+    }
+
+    #[test]
+    fn test_notify() {
+        let mut w = Vec::new();
+        let mut n = Notification::new("done");
+        n.id = Some("1");
+        n.body = Some("ok");
+        n.urgency = Some(Urgency::Critical);
+        n.report = true;
+        notify(&mut w, &n).unwrap();
+        assert_eq!(
+            String::from_utf8(w).unwrap(),
+            "\x1b]99;i=1:a=focus,report:u=2:d=0:e=1:p=title;ZG9uZQ==\x1b\\\
+             \x1b]99;i=1:d=1:e=1:p=body;b2s=\x1b\\"
+        );
+
+        n.id = Some("1:p=close");
+        assert!(notify(&mut Vec::new(), &n).is_err());
+        n.id = Some("a\x1b\\");
+        assert!(notify(&mut Vec::new(), &n).is_err());
+        n.id = Some("build-1.2_x+y");
+        assert!(notify(&mut Vec::new(), &n).is_ok());
+
+        let mut w = Vec::new();
+        notify_either(&mut w, &n, false).unwrap();
+        assert_eq!(w, b"\x1b]9;done: ok\x1b\\");
+    }
+
+    #[test]
+    fn test_notify_supported() {
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1b]99;i=ansi-csi:p=?;a=focus,report:o=always\x1b\\\x1b[?62c";
+        assert_eq!(notify_supported_reply(&mut w, &mut r), Some(true));
+        assert_eq!(w, b"\x1b]99;i=ansi-csi:p=?;\x1b\\\x1b[c");
+        let mut r: &[u8] = b"\x1b]99;i=1;\x1b\\\x1b[?62c";
+        assert_eq!(notify_supported_reply(&mut w, &mut r), Some(false));
+    }
+
+    #[test]
+    fn test_parse_notification_report() {
+        assert_eq!(
+            parse_notification_report(b"\x1b]99;i=1;\x1b\\"),
+            Some(NotificationReport::Activated("1".to_string()))
+        );
+        assert_eq!(
+            parse_notification_report(b"\x1b]99;i=1:p=close;\x07"),
+            Some(NotificationReport::Closed("1".to_string()))
+        );
+        assert_eq!(parse_notification_report(b"\x1b]99;;\x1b\\"), None);
+    }
+}
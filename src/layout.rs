@@ -0,0 +1,129 @@
+// Screen layout with splits into rectangles
+
+/// A screen area, zero based cell position and size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Side by side, splitting the width
+    Horizontal,
+    /// Stacked, splitting the height
+    Vertical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constraint {
+    /// Fixed number of cells
+    Fixed(usize),
+    /// Percentage of the whole size
+    Percentage(u8),
+    /// Share of what is left after Fixed and Percentage
+    Fill,
+}
+
+impl Rect {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Split into one rectangle per constraint along direction.
+    /// Sizes are cut to the available space in order, Fill parts share the rest evenly.
+    pub fn split(&self, direction: Direction, constraints: &[Constraint]) -> Vec<Rect> {
+        let total = match direction {
+            Direction::Horizontal => self.width,
+            Direction::Vertical => self.height,
+        };
+        let mut left = total;
+        let mut sizes: Vec<usize> = constraints
+            .iter()
+            .map(|c| {
+                let size = match *c {
+                    Constraint::Fixed(n) => n,
+                    Constraint::Percentage(p) => total * usize::from(p.min(100)) / 100,
+                    Constraint::Fill => 0,
+                }
+                .min(left);
+                left -= size;
+                size
+            })
+            .collect();
+        let fills = constraints
+            .iter()
+            .filter(|c| **c == Constraint::Fill)
+            .count();
+        let mut nth = 0;
+        for (size, c) in sizes.iter_mut().zip(constraints) {
+            if *c == Constraint::Fill {
+                // earlier fills take the remainder
+                *size = left / fills + usize::from(nth < left % fills);
+                nth += 1;
+            }
+        }
+        let mut offset = 0;
+        sizes
+            .into_iter()
+            .map(|size| {
+                let r = match direction {
+                    Direction::Horizontal => Rect::new(self.x + offset, self.y, size, self.height),
+                    Direction::Vertical => Rect::new(self.x, self.y + offset, self.width, size),
+                };
+                offset += size;
+                r
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let screen = Rect::new(0, 0, 80, 24);
+        let rows = screen.split(
+            Direction::Vertical,
+            &[Constraint::Fixed(1), Constraint::Fill, Constraint::Fixed(1)],
+        );
+        assert_eq!(
+            rows,
+            vec![
+                Rect::new(0, 0, 80, 1),
+                Rect::new(0, 1, 80, 22),
+                Rect::new(0, 23, 80, 1)
+            ]
+        );
+        let cols = rows[1].split(
+            Direction::Horizontal,
+            &[
+                Constraint::Percentage(25),
+                Constraint::Fill,
+                Constraint::Fill,
+            ],
+        );
+        assert_eq!(
+            cols,
+            vec![
+                Rect::new(0, 1, 20, 22),
+                Rect::new(20, 1, 30, 22),
+                Rect::new(50, 1, 30, 22)
+            ]
+        );
+        let cut = Rect::new(0, 0, 10, 5).split(
+            Direction::Horizontal,
+            &[Constraint::Fixed(8), Constraint::Fixed(8)],
+        );
+        assert_eq!(cut, vec![Rect::new(0, 0, 8, 5), Rect::new(8, 0, 2, 5)]);
+    }
+}
@@ -0,0 +1,65 @@
+// Record line shared by the log and tracing integrations
+
+use crate::csi::{self, SgrCode};
+use std::fmt;
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    fn color(self) -> SgrCode {
+        match self {
+            Level::Error => SgrCode::FgColorRed,
+            Level::Warn => SgrCode::FgColorYellow,
+            Level::Info => SgrCode::FgColorGreen,
+            Level::Debug => SgrCode::FgColorBlue,
+            Level::Trace => SgrCode::FgColorBrightBlack,
+        }
+    }
+}
+
+// "LEVEL span:span: target: message" with the level colored and spans in bold
+pub(crate) fn write_line<W: io::Write>(
+    w: &mut W,
+    level: Level,
+    spans: &[&str],
+    target: &str,
+    message: fmt::Arguments,
+    color: bool,
+) -> io::Result<()> {
+    if color {
+        csi::sgr(w, level.color())?;
+        write!(w, "{:5}", level.name())?;
+        csi::sgr(w, SgrCode::Normal)?;
+    } else {
+        write!(w, "{:5}", level.name())?;
+    }
+    for span in spans {
+        if color {
+            csi::sgr(w, SgrCode::Bold)?;
+            write!(w, " {}", span)?;
+            csi::sgr(w, SgrCode::BoldFaintOff)?;
+            write!(w, ":")?;
+        } else {
+            write!(w, " {}:", span)?;
+        }
+    }
+    writeln!(w, " {}: {}", target, message)
+}
@@ -1,6 +1,40 @@
+extern crate libc;
 extern crate termios;
 
+mod base64;
+#[cfg(any(feature = "log", feature = "tracing"))]
+mod level;
+#[macro_use]
 pub mod csi;
+#[macro_use]
+pub mod dcs;
+#[macro_use]
+pub mod esc;
+#[macro_use]
+pub mod osc;
+pub mod blocks;
+pub mod canvas;
+pub mod detect;
+pub mod diagnostic;
+pub mod hyperlink;
+pub mod input;
+#[cfg(feature = "iterm2")]
+pub mod iterm2;
+pub mod kitty;
+pub mod layout;
+#[cfg(feature = "log")]
+pub mod logger;
+pub mod palette;
+pub mod prompt;
+#[cfg(feature = "qr")]
+pub mod qr;
+pub mod record;
+pub mod split;
+pub mod text;
+pub mod timeout;
+pub mod tmux;
+#[cfg(feature = "tracing")]
+pub mod trace;
 
 /// echo off. Return old termios state.
 pub fn echo_off() -> termios::Termios {
@@ -15,3 +49,36 @@ pub fn echo_off() -> termios::Termios {
 pub fn echo_on(termstat: &termios::Termios) {
     termios::tcsetattr(0, termios::TCSANOW, &termstat).unwrap();
 }
+
+/// Run a child process with the terminal given back to it, like shelling out to $EDITOR from a TUI.
+/// Terminal state is switched to termstat (e.g. the one returned by echo_off) and
+/// the alternate screen is left if alt_screen is true while the child is running.
+/// Terminal state and the alternate screen are restored after the child exits, even if one of them fails,
+/// and the first error is returned. Entering the alternate screen again clears it, so the caller must redraw.
+/// Cursor visibility and input modes such as bracketed paste, mouse reporting and keyboard enhancement
+/// are left as they are. Turn them off before and back on after the call if the child shouldn't get them.
+pub fn run_child_restoring_terminal<W: std::io::Write>(
+    w: &mut W,
+    cmd: &mut std::process::Command,
+    termstat: &termios::Termios,
+    alt_screen: bool,
+) -> std::io::Result<std::process::ExitStatus> {
+    let curstat = termios::Termios::from_fd(0)?;
+    if alt_screen {
+        csi::alt_screen_leave(w)?;
+    }
+    w.flush()?;
+    termios::tcsetattr(0, termios::TCSANOW, termstat)?;
+    let status = cmd.status();
+    let restored = termios::tcsetattr(0, termios::TCSANOW, &curstat);
+    let entered = if alt_screen {
+        csi::alt_screen_enter(w)
+    } else {
+        Ok(())
+    }
+    .and_then(|_| w.flush());
+    let status = status?;
+    restored?;
+    entered?;
+    Ok(status)
+}
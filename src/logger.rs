@@ -0,0 +1,97 @@
+// Colorized logger for the log crate
+
+use crate::level;
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::io::{self, Write};
+
+/// Logger writing records to stderr with the level colored.
+pub struct Logger {
+    level: LevelFilter,
+    color: bool,
+}
+
+impl Logger {
+    /// Color is enabled if stderr is a terminal.
+    pub fn new(level: LevelFilter) -> Logger {
+        let color = unsafe { libc::isatty(2) } == 1;
+        Logger { level, color }
+    }
+
+    /// Level is taken from RUST_LOG (e.g. "debug"), defaults to error.
+    pub fn from_env() -> Logger {
+        let level = std::env::var("RUST_LOG")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(LevelFilter::Error);
+        Logger::new(level)
+    }
+
+    pub fn color(mut self, color: bool) -> Logger {
+        self.color = color;
+        self
+    }
+
+    /// Install as the global logger.
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        log::set_max_level(self.level);
+        log::set_boxed_logger(Box::new(self))
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let w = io::stderr();
+            let mut w = w.lock();
+            let _ = format(&mut w, record, self.color);
+        }
+    }
+
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
+
+/// Write a record as "LEVEL target: message".
+pub fn format<W: io::Write>(w: &mut W, record: &Record, color: bool) -> io::Result<()> {
+    let level = match record.level() {
+        Level::Error => level::Level::Error,
+        Level::Warn => level::Level::Warn,
+        Level::Info => level::Level::Info,
+        Level::Debug => level::Level::Debug,
+        Level::Trace => level::Level::Trace,
+    };
+    level::write_line(w, level, &[], record.target(), *record.args(), color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_record(w: &mut Vec<u8>, color: bool) {
+        format(
+            w,
+            &Record::builder()
+                .level(Level::Warn)
+                .target("app")
+                .args(format_args!("disk {}% full", 90))
+                .build(),
+            color,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_format() {
+        let mut w = Vec::new();
+        write_record(&mut w, true);
+        assert_eq!(w, &b"\x1b[33mWARN \x1b[0m app: disk 90% full\n"[..]);
+        w.clear();
+        write_record(&mut w, false);
+        assert_eq!(w, b"WARN  app: disk 90% full\n");
+    }
+}
@@ -0,0 +1,212 @@
+// OSC(Operating System Command) of Escape sequence
+
+use crate::base64;
+use crate::csi::{query, read_until_da1, string_payload, ReadReply};
+use std::io;
+
+#[macro_export]
+macro_rules! osc {
+    ($( $s:expr ),*) => { concat!("\x1b]", $( $s ),*, "\x1b\\") };
+}
+
+/// Selection buffer of OSC 52.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Clipboard,
+    Primary,
+    Secondary,
+    Select,
+    CutBuffer0,
+    CutBuffer1,
+    CutBuffer2,
+    CutBuffer3,
+    CutBuffer4,
+    CutBuffer5,
+    CutBuffer6,
+    CutBuffer7,
+}
+
+impl Selection {
+    fn as_char(self) -> char {
+        match self {
+            Selection::Clipboard => 'c',
+            Selection::Primary => 'p',
+            Selection::Secondary => 'q',
+            Selection::Select => 's',
+            Selection::CutBuffer0 => '0',
+            Selection::CutBuffer1 => '1',
+            Selection::CutBuffer2 => '2',
+            Selection::CutBuffer3 => '3',
+            Selection::CutBuffer4 => '4',
+            Selection::CutBuffer5 => '5',
+            Selection::CutBuffer6 => '6',
+            Selection::CutBuffer7 => '7',
+        }
+    }
+}
+
+/// Default limit of the base64 encoded payload, a policy of this crate rather than a standard.
+/// hterm (hterm.VT.maxStringSequence) drops OSC strings longer than 100000 bytes,
+/// the lowest limit among common terminals. This is the largest payload that fits after "52;c;".
+pub const CLIPBOARD_MAX_LEN: usize = 99_992;
+
+/// Title length which is accepted by most terminals.
+pub const TITLE_MAX_LEN: usize = 255;
+
+/// Set window title.
+/// title is written as is. Use `set_title_safe` for untrusted strings.
+pub fn set_title<W: io::Write>(w: &mut W, title: &str) -> io::Result<()> {
+    w.write_fmt(format_args!(osc!("2;{}"), title))?;
+    Ok(())
+}
+
+/// Report window title.
+/// The title can be saved and restored by `set_title` later, even on terminals without the title stack.
+/// DA1 is sent after the query, so None is returned instead of blocking
+/// when the terminal doesn't report the title.
+/// A title longer than the reply limit is skipped too, pass a `ReplyReader` to raise the limit
+/// or to tell it from an unsupported query.
+pub fn title<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<String> {
+    query(w, r, title_reply)
+}
+
+// => "\x1b]l${title}\x1b\\" (if allowed) and "\x1b[?${attrs}c"
+fn title_reply<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<String> {
+    w.write_fmt(format_args!(concat!(csi!("21t"), csi!("c"))))
+        .ok()?;
+    w.flush().ok()?;
+    let mut title = None;
+    read_until_da1(r, |seq| {
+        if let Some(t) = string_payload(seq, b"\x1b]l") {
+            title = Some(String::from_utf8_lossy(t).into_owned());
+        }
+    })?;
+    title
+}
+
+/// Set window title from arbitrary bytes.
+/// The title is sanitized by `sanitize_title` before written.
+pub fn set_title_safe<W: io::Write>(w: &mut W, title: &[u8], max_len: usize) -> io::Result<()> {
+    set_title(w, &sanitize_title(title, max_len))
+}
+
+/// Make title safe to be embedded into OSC.
+/// Invalid UTF-8 is replaced with U+FFFD, newlines and tabs become spaces,
+/// other control characters are removed and the result is truncated to max_len characters.
+pub fn sanitize_title(title: &[u8], max_len: usize) -> String {
+    String::from_utf8_lossy(title)
+        .chars()
+        .filter_map(|c| match c {
+            '\n' | '\r' | '\t' => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .take(max_len)
+        .collect()
+}
+
+/// Set data to the selection buffer with OSC 52.
+/// Data is base64 encoded. If the encoded data is longer than max_len, nothing is written
+/// and an InvalidInput error is returned, since terminals silently drop or truncate oversized payloads.
+pub fn clipboard_set<W: io::Write>(
+    w: &mut W,
+    s: Selection,
+    data: &[u8],
+    max_len: usize,
+) -> io::Result<()> {
+    if base64::encoded_len(data.len()) > max_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "clipboard payload is too large",
+        ));
+    }
+    w.write_fmt(format_args!(
+        osc!("52;{};{}"),
+        s.as_char(),
+        base64::encode(data)
+    ))?;
+    Ok(())
+}
+
+/// Set a user variable (OSC 1337 SetUserVar) consumed by WezTerm and iTerm2,
+/// e.g. to publish application state to their status bars. value is base64 encoded.
+pub fn set_user_var<W: io::Write>(w: &mut W, name: &str, value: &str) -> io::Result<()> {
+    w.write_fmt(format_args!(
+        osc!("1337;SetUserVar={}={}"),
+        name,
+        base64::encode(value.as_bytes())
+    ))?;
+    Ok(())
+}
+
+/// Post a desktop notification (OSC 9).
+/// message is sanitized as by `sanitize_title`, so control characters can't end the sequence.
+pub fn notify<W: io::Write>(w: &mut W, message: &str) -> io::Result<()> {
+    w.write_fmt(format_args!(
+        osc!("9;{}"),
+        sanitize_title(message.as_bytes(), usize::MAX)
+    ))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_title() {
+        let mut w = Vec::new();
+        set_title(&mut w, "hello").unwrap();
+        assert_eq!(w, b"\x1b]2;hello\x1b\\");
+    }
+
+    #[test]
+    fn test_sanitize_title() {
+        assert_eq!(sanitize_title(b"a\nb\tc", TITLE_MAX_LEN), "a b c");
+        assert_eq!(sanitize_title(b"\x1b]0;x\x07y", TITLE_MAX_LEN), "]0;xy");
+        assert_eq!(sanitize_title(b"ab\xffc", TITLE_MAX_LEN), "ab\u{fffd}c");
+        assert_eq!(sanitize_title("あいう".as_bytes(), 2), "あい");
+    }
+
+    #[test]
+    fn test_clipboard_set() {
+        let mut w = Vec::new();
+        clipboard_set(&mut w, Selection::Clipboard, b"foo", CLIPBOARD_MAX_LEN).unwrap();
+        assert_eq!(w, b"\x1b]52;c;Zm9v\x1b\\");
+        w.clear();
+        clipboard_set(&mut w, Selection::CutBuffer7, b"f", CLIPBOARD_MAX_LEN).unwrap();
+        assert_eq!(w, b"\x1b]52;7;Zg==\x1b\\");
+        w.clear();
+        assert!(clipboard_set(&mut w, Selection::Primary, b"foob", 4).is_err());
+        assert_eq!(w, b"");
+    }
+
+    #[test]
+    fn test_title() {
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"\x1b]lvim main.rs\x1b\\\x1b[?62;22c";
+        assert_eq!(title_reply(&mut w, &mut r), Some("vim main.rs".to_string()));
+        assert_eq!(w, b"\x1b[21t\x1b[c");
+        let mut r: &[u8] = b"\x1b]lbash\x07\x1b[?1;2c";
+        assert_eq!(title_reply(&mut w, &mut r), Some("bash".to_string()));
+        let mut r: &[u8] = b"\x1b[?1;2c";
+        assert_eq!(title_reply(&mut w, &mut r), None);
+    }
+
+    #[test]
+    fn test_set_user_var() {
+        let mut w = Vec::new();
+        set_user_var(&mut w, "mode", "insert").unwrap();
+        assert_eq!(w, b"\x1b]1337;SetUserVar=mode=aW5zZXJ0\x1b\\");
+    }
+
+    #[test]
+    fn test_notify() {
+        let mut w = Vec::new();
+        notify(&mut w, "build finished").unwrap();
+        assert_eq!(w, b"\x1b]9;build finished\x1b\\");
+        w.clear();
+        notify(&mut w, "a\x07b\x1b\\\nc").unwrap();
+        assert_eq!(w, b"\x1b]9;ab\\ c\x1b\\");
+    }
+}
@@ -0,0 +1,186 @@
+// 256 color palette
+
+use crate::csi::{query, read_until_da1, string_payload, ReadReply};
+use std::io;
+
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// RGB colors of the 256 color indexes.
+#[derive(Clone)]
+pub struct Palette {
+    colors: [(u8, u8, u8); 256],
+}
+
+impl Palette {
+    /// xterm default palette.
+    /// 0-15: ANSI colors, 16-231: 6x6x6 color cube, 232-255: grayscale
+    pub fn xterm() -> Palette {
+        let mut colors = [(0, 0, 0); 256];
+        colors[..16].copy_from_slice(&ANSI16);
+        for i in 0..216 {
+            colors[16 + i] = (
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[i / 6 % 6],
+                CUBE_LEVELS[i % 6],
+            );
+        }
+        for i in 0..24 {
+            let v = 8 + 10 * i as u8;
+            colors[232 + i] = (v, v, v);
+        }
+        Palette { colors }
+    }
+
+    pub fn get(&self, index: u8) -> (u8, u8, u8) {
+        self.colors[usize::from(index)]
+    }
+
+    pub fn set(&mut self, index: u8, rgb: (u8, u8, u8)) {
+        self.colors[usize::from(index)] = rgb;
+    }
+
+    /// Index of the color nearest to rgb in euclidean distance.
+    /// The lower index wins a tie, so exact ANSI colors are preferred to the cube.
+    pub fn nearest(&self, rgb: (u8, u8, u8)) -> u8 {
+        let dist = |c: &(u8, u8, u8)| {
+            let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+            d(c.0, rgb.0) + d(c.1, rgb.1) + d(c.2, rgb.2)
+        };
+        let mut best = (0, i32::MAX);
+        for (i, c) in self.colors.iter().enumerate() {
+            let d = dist(c);
+            if d < best.1 {
+                best = (i, d);
+            }
+        }
+        best.0 as u8
+    }
+
+    /// Read the terminal's current colors with OSC 4, e.g. to restore them with apply at exit.
+    /// Colors the terminal doesn't report keep the xterm defaults.
+    /// DA1 is sent after the queries, so None is returned instead of blocking
+    /// when the terminal reports no color.
+    pub fn capture<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<Palette> {
+        query(w, r, capture_reply)
+    }
+
+    /// Set all 256 colors of the terminal with OSC 4.
+    pub fn apply<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        for (i, (r, g, b)) in self.colors.iter().enumerate() {
+            w.write_fmt(format_args!(
+                osc!("4;{};rgb:{:02x}/{:02x}/{:02x}"),
+                i, r, g, b
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+// => "\x1b]4;${index};rgb:${r}/${g}/${b}\x1b\\" for each color and "\x1b[?${attrs}c"
+fn capture_reply<W: io::Write, R: ReadReply>(w: &mut W, r: &mut R) -> Option<Palette> {
+    for i in 0..256 {
+        w.write_fmt(format_args!(osc!("4;{};?"), i)).ok()?;
+    }
+    w.write_fmt(format_args!(csi!("c"))).ok()?;
+    w.flush().ok()?;
+    let (mut palette, mut captured) = (Palette::xterm(), false);
+    read_until_da1(r, |seq| {
+        if let Some(body) = string_payload(seq, b"\x1b]4;") {
+            if let Some((i, rgb)) = parse_color_report(body) {
+                palette.set(i, rgb);
+                captured = true;
+            }
+        }
+    })?;
+    if captured {
+        Some(palette)
+    } else {
+        None
+    }
+}
+
+// "${index};rgb:${r}/${g}/${b}", each component is 1 to 4 hex digits
+fn parse_color_report(body: &[u8]) -> Option<(u8, (u8, u8, u8))> {
+    let body = std::str::from_utf8(body).ok()?;
+    let (index, spec) = body.split_once(';')?;
+    let mut cs = spec.strip_prefix("rgb:")?.split('/').map(|c| {
+        let v = u32::from_str_radix(c, 16).ok()?;
+        let max = match c.len() {
+            1..=4 => (1u32 << (4 * c.len())) - 1,
+            _ => return None,
+        };
+        Some((v * 255 / max) as u8)
+    });
+    let rgb = (cs.next()??, cs.next()??, cs.next()??);
+    Some((index.parse().ok()?, rgb))
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette::xterm()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xterm() {
+        let p = Palette::xterm();
+        assert_eq!(p.get(1), (205, 0, 0));
+        assert_eq!(p.get(16), (0, 0, 0));
+        assert_eq!(p.get(196), (255, 0, 0));
+        assert_eq!(p.get(231), (255, 255, 255));
+        assert_eq!(p.get(232), (8, 8, 8));
+        assert_eq!(p.get(255), (238, 238, 238));
+    }
+
+    #[test]
+    fn test_nearest() {
+        let mut p = Palette::xterm();
+        assert_eq!(p.nearest((255, 0, 0)), 9);
+        assert_eq!(p.nearest((0, 0, 0)), 0);
+        assert_eq!(p.nearest((100, 100, 100)), 241);
+        assert_eq!(p.nearest((96, 135, 214)), 68);
+        p.set(200, (1, 2, 3));
+        assert_eq!(p.nearest((1, 2, 3)), 200);
+    }
+
+    #[test]
+    fn test_capture_apply() {
+        let mut w = Vec::new();
+        let mut r: &[u8] =
+            b"\x1b]4;1;rgb:ffff/8080/0000\x1b\\\x1b]4;255;rgb:12/34/56\x07\x1b[?62;22c";
+        let p = capture_reply(&mut w, &mut r).unwrap();
+        assert!(w.starts_with(b"\x1b]4;0;?\x1b\\\x1b]4;1;?\x1b\\"));
+        assert!(w.ends_with(b"\x1b]4;255;?\x1b\\\x1b[c"));
+        assert_eq!(p.get(1), (255, 128, 0));
+        assert_eq!(p.get(255), (0x12, 0x34, 0x56));
+        assert_eq!(p.get(2), (0, 205, 0));
+        let mut r: &[u8] = b"\x1b[?62;22c";
+        assert!(capture_reply(&mut w, &mut r).is_none());
+        let mut w = Vec::new();
+        p.apply(&mut w).unwrap();
+        assert!(w.starts_with(b"\x1b]4;0;rgb:00/00/00\x1b\\\x1b]4;1;rgb:ff/80/00\x1b\\"));
+    }
+}
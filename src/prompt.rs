@@ -0,0 +1,134 @@
+// Line based prompts
+// Input is read line by line, so they work the same when stdin is not a terminal.
+
+use crate::csi::{self, SgrCode};
+use std::io;
+
+/// Ask a yes/no question.
+/// Empty answer or end of input gives default. Other answers than y/yes/n/no are asked again.
+pub fn confirm<W: io::Write, R: io::BufRead>(
+    w: &mut W,
+    r: &mut R,
+    prompt: &str,
+    default: bool,
+) -> io::Result<bool> {
+    let choices = if default { "[Y/n]" } else { "[y/N]" };
+    loop {
+        write!(w, "{} {} ", prompt, choices)?;
+        w.flush()?;
+        let line = match read_line(r)? {
+            Some(line) => line,
+            None => return Ok(default),
+        };
+        match line.trim().to_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => {}
+        }
+    }
+}
+
+/// Ask for a line of text.
+/// Empty answer gives default if any. The answer is asked again while validate returns an error message,
+/// which is shown in red if color is true. Pass false when the output is not a terminal.
+/// End of input gives default without validating it, or an UnexpectedEof error without default.
+pub fn input<W, R, F>(
+    w: &mut W,
+    r: &mut R,
+    prompt: &str,
+    default: Option<&str>,
+    validate: F,
+    color: bool,
+) -> io::Result<String>
+where
+    W: io::Write,
+    R: io::BufRead,
+    F: Fn(&str) -> Result<(), String>,
+{
+    loop {
+        match default {
+            Some(d) => write!(w, "{} ({}) ", prompt, d)?,
+            None => write!(w, "{} ", prompt)?,
+        }
+        w.flush()?;
+        let line = match (read_line(r)?, default) {
+            (Some(line), _) => line,
+            (None, Some(d)) => return Ok(d.to_string()),
+            (None, None) => return Err(io::ErrorKind::UnexpectedEof.into()),
+        };
+        let answer = match (line.as_str(), default) {
+            ("", Some(d)) => d.to_string(),
+            _ => line,
+        };
+        match validate(&answer) {
+            Ok(()) => return Ok(answer),
+            Err(msg) if color => {
+                csi::sgr(w, SgrCode::FgColorRed)?;
+                write!(w, "{}", msg)?;
+                csi::sgr(w, SgrCode::Normal)?;
+                writeln!(w)?;
+            }
+            Err(msg) => {
+                writeln!(w, "{}", msg)?;
+            }
+        }
+    }
+}
+
+// read a line without the line ending, None at end of input
+fn read_line<R: io::BufRead>(r: &mut R) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if r.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let len = line.trim_end_matches(&['\r', '\n'][..]).len();
+    line.truncate(len);
+    Ok(Some(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirm() {
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"maybe\nyes\n\n";
+        assert!(confirm(&mut w, &mut r, "Continue?", false).unwrap());
+        assert_eq!(w, b"Continue? [y/N] Continue? [y/N] ");
+        assert!(confirm(&mut w, &mut r, "Continue?", true).unwrap());
+        assert!(!confirm(&mut w, &mut r, "Continue?", false).unwrap());
+    }
+
+    #[test]
+    fn test_input() {
+        let number = |s: &str| {
+            s.parse::<u32>()
+                .map(|_| ())
+                .map_err(|_| "not a number".to_string())
+        };
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"abc\n42\n";
+        assert_eq!(
+            input(&mut w, &mut r, "Port:", None, number, true).unwrap(),
+            "42"
+        );
+        assert_eq!(w, &b"Port: \x1b[31mnot a number\x1b[0m\nPort: "[..]);
+        let mut r: &[u8] = b"\n";
+        assert_eq!(
+            input(&mut w, &mut r, "Port:", Some("80"), number, false).unwrap(),
+            "80"
+        );
+        let mut r: &[u8] = b"";
+        assert_eq!(
+            input(&mut w, &mut r, "Port:", Some("80"), number, false).unwrap(),
+            "80"
+        );
+        assert!(input(&mut w, &mut r, "Port:", None, number, false).is_err());
+        let mut w = Vec::new();
+        let mut r: &[u8] = b"abc\n1\n";
+        input(&mut w, &mut r, "Port:", None, number, false).unwrap();
+        assert_eq!(w, b"Port: not a number\nPort: ");
+    }
+}
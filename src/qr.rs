@@ -0,0 +1,119 @@
+// Rendering of QR codes with half block characters
+// Data is encoded with the qrcodegen crate.
+
+use crate::csi::{self, SgrCode};
+use qrcodegen::{QrCode, QrCodeEcc};
+use std::io;
+
+/// Width of the quiet zone around the symbol in modules, as required by the QR specification.
+pub const QUIET_ZONE: usize = 4;
+
+/// Columns needed to draw a symbol of size x size modules, including the quiet zone.
+pub fn columns(size: usize) -> usize {
+    size + 2 * QUIET_ZONE
+}
+
+/// Lines needed to draw a symbol of size x size modules, including the quiet zone.
+pub fn lines(size: usize) -> usize {
+    columns(size).div_ceil(2)
+}
+
+/// Encode data into the smallest QR symbol and draw it as render does.
+/// Return an InvalidInput error if data is too long for a QR code.
+pub fn render_data<W: io::Write>(
+    w: &mut W,
+    data: &[u8],
+    max_cols: usize,
+    max_lines: usize,
+) -> io::Result<()> {
+    // low error correction keeps the symbol small, a screen isn't smudged like paper
+    let qr = QrCode::encode_binary(data, QrCodeEcc::Low)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "data is too long"))?;
+    let size = qr.size();
+    let modules: Vec<bool> = (0..size)
+        .flat_map(|y| (0..size).map(move |x| (x, y)))
+        .map(|(x, y)| qr.get_module(x, y))
+        .collect();
+    render(w, &modules, size as usize, max_cols, max_lines)
+}
+
+/// Draw a QR symbol, modules is size x size in rows with true for dark modules.
+/// Two modules are drawn per cell with half blocks in black on white, whatever the terminal colors are.
+/// Rows are separated by CR LF. Return an InvalidInput error if it doesn't fit in
+/// max_cols x max_lines, e.g. the terminal size.
+pub fn render<W: io::Write>(
+    w: &mut W,
+    modules: &[bool],
+    size: usize,
+    max_cols: usize,
+    max_lines: usize,
+) -> io::Result<()> {
+    if modules.len() != size * size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "modules is not size x size",
+        ));
+    }
+    if columns(size) > max_cols || lines(size) > max_lines {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "QR code is larger than the terminal",
+        ));
+    }
+    let total = columns(size);
+    // coordinates including the quiet zone
+    let dark = |x: usize, y: usize| {
+        let (x, y) = (x.wrapping_sub(QUIET_ZONE), y.wrapping_sub(QUIET_ZONE));
+        x < size && y < size && modules[y * size + x]
+    };
+    for y in (0..total).step_by(2) {
+        if y > 0 {
+            w.write_all(b"\r\n")?;
+        }
+        csi::sgr(w, SgrCode::FgColorBlack)?;
+        csi::sgr(w, SgrCode::BgColorBrightWhite)?;
+        for x in 0..total {
+            let c = match (dark(x, y), dark(x, y + 1)) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            };
+            write!(w, "{}", c)?;
+        }
+        csi::sgr(w, SgrCode::Normal)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut w = Vec::new();
+        render(&mut w, &[true, false, true, true], 2, 80, 24).unwrap();
+        let s = String::from_utf8(w).unwrap();
+        let lines: Vec<&str> = s.split("\r\n").collect();
+        assert_eq!(lines.len(), 5);
+        let blank = format!("\x1b[30m\x1b[107m{}\x1b[0m", " ".repeat(10));
+        assert_eq!(lines[0], blank);
+        assert_eq!(lines[2], "\x1b[30m\x1b[107m    █▄    \x1b[0m");
+        assert_eq!(lines[4], blank);
+        assert!(render(&mut Vec::new(), &[true; 4], 2, 9, 24).is_err());
+        assert!(render(&mut Vec::new(), &[true; 4], 2, 80, 4).is_err());
+        assert!(render(&mut Vec::new(), &[true; 3], 2, 80, 24).is_err());
+    }
+
+    #[test]
+    fn test_render_data() {
+        // version 1 is 21 x 21 modules
+        let mut w = Vec::new();
+        render_data(&mut w, b"hello", 80, 24).unwrap();
+        let s = String::from_utf8(w).unwrap();
+        assert_eq!(s.split("\r\n").count(), lines(21));
+        assert!(render_data(&mut Vec::new(), b"hello", 28, 24).is_err());
+        assert!(render_data(&mut Vec::new(), &[0; 3000], 1000, 1000).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+// Recording of written sequences
+
+use std::collections::VecDeque;
+use std::io;
+use std::panic;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Writer which passes data through to the inner writer and copies it to a sink.
+/// Errors of the sink are ignored, so recording never breaks the output.
+pub struct Recorder<W: io::Write, S: io::Write> {
+    inner: W,
+    sink: S,
+}
+
+impl<W: io::Write, S: io::Write> Recorder<W, S> {
+    pub fn wrap(inner: W, sink: S) -> Recorder<W, S> {
+        Recorder { inner, sink }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    pub fn into_inner(self) -> (W, S) {
+        (self.inner, self.sink)
+    }
+}
+
+impl<W: io::Write, S: io::Write> io::Write for Recorder<W, S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        let _ = self.sink.write_all(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let _ = self.sink.flush();
+        self.inner.flush()
+    }
+}
+
+/// Source of timestamps for RingRecorder.
+/// Tests can inject ManualClock to get deterministic timings.
+pub trait Clock: Send {
+    /// Time elapsed since an arbitrary fixed origin.
+    fn now(&self) -> Duration;
+}
+
+/// Clock of real time, starting when it's created.
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Clock which moves only when told to. Clones share the time.
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock::default()
+    }
+
+    pub fn advance(&self, d: Duration) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) += d;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Sink keeping only the last capacity bytes of written data with timestamps.
+/// Clones share the buffer, so one can be given to a Recorder and another dumped later,
+/// e.g. from a panic hook to diagnose a terminal left in a weird state.
+#[derive(Clone)]
+pub struct RingRecorder {
+    ring: Arc<Mutex<Ring>>,
+}
+
+struct Ring {
+    clock: Box<dyn Clock>,
+    capacity: usize,
+    len: usize,
+    chunks: VecDeque<(Duration, Vec<u8>)>,
+}
+
+impl RingRecorder {
+    pub fn new(capacity: usize) -> RingRecorder {
+        RingRecorder::with_clock(capacity, SystemClock::new())
+    }
+
+    /// Timestamp chunks with clock instead of the real time.
+    pub fn with_clock<C: Clock + 'static>(capacity: usize, clock: C) -> RingRecorder {
+        let ring = Ring {
+            clock: Box::new(clock),
+            capacity,
+            len: 0,
+            chunks: VecDeque::new(),
+        };
+        RingRecorder {
+            ring: Arc::new(Mutex::new(ring)),
+        }
+    }
+
+    /// Write recorded chunks, one per line, as elapsed seconds and escaped bytes.
+    pub fn dump<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        // dump is called from panic hooks, so a poisoned lock is still used
+        let ring = self.ring.lock().unwrap_or_else(|e| e.into_inner());
+        for (t, data) in ring.chunks.iter() {
+            write!(w, "[{:>4}.{:06}] ", t.as_secs(), t.subsec_micros())?;
+            for &b in data {
+                write!(w, "{}", std::ascii::escape_default(b))?;
+            }
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+
+    /// Write recorded chunks to w, calling wait with the recorded interval before each one.
+    /// Pass `|_| {}` to replay instantly, `thread::sleep` for real time,
+    /// or a function advancing a ManualClock to drive time-dependent rendering in tests.
+    pub fn replay<W: io::Write, F: FnMut(Duration)>(
+        &self,
+        w: &mut W,
+        mut wait: F,
+    ) -> io::Result<()> {
+        let chunks = self
+            .ring
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .chunks
+            .clone();
+        let mut prev = chunks.front().map_or(Duration::ZERO, |c| c.0);
+        for (t, data) in chunks {
+            wait(t.saturating_sub(prev));
+            prev = t;
+            w.write_all(&data)?;
+        }
+        w.flush()
+    }
+
+    /// Dump to stderr on panic, after the previous hook is called.
+    pub fn install_panic_hook(&self) {
+        let ring = self.clone();
+        let prev = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            prev(info);
+            let _ = ring.dump(&mut io::stderr());
+        }));
+    }
+}
+
+impl io::Write for RingRecorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut ring = self.ring.lock().unwrap_or_else(|e| e.into_inner());
+        if ring.capacity == 0 || buf.is_empty() {
+            return Ok(buf.len());
+        }
+        let keep = &buf[buf.len().saturating_sub(ring.capacity)..];
+        let t = ring.clock.now();
+        ring.len += keep.len();
+        ring.chunks.push_back((t, keep.to_vec()));
+        while ring.len > ring.capacity {
+            let over = ring.len - ring.capacity;
+            let front = &mut ring.chunks.front_mut().expect("len > 0").1;
+            if front.len() <= over {
+                let n = front.len();
+                ring.chunks.pop_front();
+                ring.len -= n;
+            } else {
+                front.drain(..over);
+                ring.len -= over;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csi;
+    use std::io::Write;
+
+    #[test]
+    fn test_recorder() {
+        let mut w = Recorder::wrap(Vec::new(), Vec::new());
+        csi::cup(&mut w, 1, 2).unwrap();
+        w.write_all(b"x").unwrap();
+        let (out, rec) = w.into_inner();
+        assert_eq!(out, b"\x1b[1;2Hx");
+        assert_eq!(rec, out);
+    }
+
+    #[test]
+    fn test_ring_recorder() {
+        let ring = RingRecorder::new(8);
+        let mut w = Recorder::wrap(Vec::new(), ring.clone());
+        w.write_all(b"abc").unwrap();
+        csi::cup(&mut w, 1, 2).unwrap();
+        let mut dump = Vec::new();
+        ring.dump(&mut dump).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+        let data: String = dump
+            .lines()
+            .map(|l| &l[l.find("] ").unwrap() + 2..])
+            .collect();
+        assert_eq!(data, "bc\\x1b[1;2H");
+
+        let mut big = ring.clone();
+        big.write_all(b"0123456789").unwrap();
+        let mut dump = Vec::new();
+        ring.dump(&mut dump).unwrap();
+        assert!(String::from_utf8(dump).unwrap().ends_with("] 23456789\n"));
+    }
+
+    #[test]
+    fn test_ring_recorder_clock() {
+        let clock = ManualClock::new();
+        let mut ring = RingRecorder::with_clock(64, clock.clone());
+        clock.advance(Duration::from_millis(1500));
+        ring.write_all(b"a").unwrap();
+        clock.advance(Duration::from_millis(250));
+        ring.write_all(b"b").unwrap();
+        let mut dump = Vec::new();
+        ring.dump(&mut dump).unwrap();
+        assert_eq!(dump, b"[   1.500000] a\n[   1.750000] b\n");
+
+        let mut out = Vec::new();
+        let mut waits = Vec::new();
+        ring.replay(&mut out, |d| waits.push(d)).unwrap();
+        assert_eq!(out, b"ab");
+        assert_eq!(waits, vec![Duration::ZERO, Duration::from_millis(250)]);
+    }
+}
@@ -0,0 +1,224 @@
+// Reading without splitting escape sequences
+
+use std::io;
+
+/// Reader which never returns data ending in the middle of an escape sequence.
+/// An incomplete sequence at the end of a read is withheld and returned with the next one.
+/// The withheld tail is returned as is at end of input, or when it alone fills the caller's buffer.
+/// A lone ESC ending a read that didn't fill the buffer is not withheld: a terminal writes a sequence
+/// at once, so it is the Esc key and waiting for the next byte would delay it until the next key.
+pub struct SplitReader<R: io::Read> {
+    inner: R,
+    pending: Vec<u8>,
+}
+
+impl<R: io::Read> SplitReader<R> {
+    pub fn new(inner: R) -> SplitReader<R> {
+        SplitReader {
+            inner,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Return the inner reader and the withheld bytes.
+    pub fn into_inner(self) -> (R, Vec<u8>) {
+        (self.inner, self.pending)
+    }
+}
+
+impl<R: io::Read> io::Read for SplitReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.pending.len().min(buf.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            if n == buf.len() {
+                self.pending.drain(..n);
+                return Ok(n);
+            }
+            // pending is kept until the read succeeds, so an error like EINTR doesn't lose it
+            let len = n + self.inner.read(&mut buf[n..])?;
+            self.pending.clear();
+            if len == n {
+                // end of input
+                return Ok(len);
+            }
+            let mut tail = incomplete_tail(&buf[..len]);
+            if tail == 1 && buf[len - 1] == 0x1b && len < buf.len() {
+                tail = 0;
+            }
+            if tail == len && len == buf.len() {
+                return Ok(len);
+            }
+            self.pending.extend_from_slice(&buf[len - tail..len]);
+            if tail < len {
+                return Ok(len - tail);
+            }
+        }
+    }
+}
+
+enum State {
+    Ground,
+    // ESC and intermediates
+    Esc(usize),
+    Csi(usize),
+    // OSC, DCS, APC, PM and SOS up to BEL or ST
+    Str(usize),
+    StrEsc(usize, usize),
+}
+
+// length of the incomplete escape sequence at the end of buf
+fn incomplete_tail(buf: &[u8]) -> usize {
+    let mut state = State::Ground;
+    for (i, &b) in buf.iter().enumerate() {
+        state = match (state, b) {
+            (State::Str(start), 0x1b) => State::StrEsc(start, i),
+            (State::Str(_), 0x07) => State::Ground,
+            (State::Str(start), _) => State::Str(start),
+            (State::StrEsc(..), b'\\') => State::Ground,
+            // ESC within a string cancels it and starts a new sequence
+            (State::StrEsc(_, esc), b) => esc_next(esc, b),
+            (_, 0x1b) => State::Esc(i),
+            (State::Esc(start), b) => esc_next(start, b),
+            (State::Csi(start), 0x20..=0x3f) => State::Csi(start),
+            _ => State::Ground,
+        };
+    }
+    match state {
+        State::Ground => 0,
+        State::Esc(start) | State::Csi(start) | State::Str(start) | State::StrEsc(start, _) => {
+            buf.len() - start
+        }
+    }
+}
+
+fn esc_next(start: usize, b: u8) -> State {
+    match b {
+        0x20..=0x2f => State::Esc(start),
+        b'[' => State::Csi(start),
+        b']' | b'P' | b'_' | b'^' | b'X' => State::Str(start),
+        0x1b => State::Esc(start + 1),
+        _ => State::Ground,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    // reader returning one chunk per read
+    struct Chunks(Vec<&'static [u8]>);
+
+    impl io::Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Ok(0);
+            }
+            let chunk = self.0.remove(0);
+            buf[..chunk.len()].copy_from_slice(chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    fn reads(chunks: Vec<&'static [u8]>) -> Vec<Vec<u8>> {
+        let mut r = SplitReader::new(Chunks(chunks));
+        let mut buf = [0u8; 64];
+        let mut out = Vec::new();
+        loop {
+            let n = r.read(&mut buf).unwrap();
+            if n == 0 {
+                return out;
+            }
+            out.push(buf[..n].to_vec());
+        }
+    }
+
+    #[test]
+    fn test_split_reader() {
+        assert_eq!(
+            reads(vec![b"ab\x1b[1;3", b"1mcd\x1b[", b"0m"]),
+            vec![
+                b"ab".to_vec(),
+                b"\x1b[1;31mcd".to_vec(),
+                b"\x1b[0m".to_vec()
+            ]
+        );
+        assert_eq!(
+            reads(vec![b"\x1b]0;ti", b"tle\x1b", b"\\x"]),
+            vec![b"\x1b]0;title\x1b\\x".to_vec()]
+        );
+        assert_eq!(
+            reads(vec![b"x\x1b(", b"Bx\x1b["]),
+            vec![b"x".to_vec(), b"\x1b(Bx".to_vec(), b"\x1b[".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_split_reader_lone_esc() {
+        // the Esc key isn't held until the next key
+        assert_eq!(
+            reads(vec![b"a\x1b", b"b"]),
+            vec![b"a\x1b".to_vec(), b"b".to_vec()]
+        );
+        assert_eq!(reads(vec![b"\x1b"]), vec![b"\x1b".to_vec()]);
+        // but is withheld when the read may have been cut by the buffer size
+        let mut r = SplitReader::new(Chunks(vec![b"abc\x1b", b"[0m"]));
+        let mut buf = [0u8; 4];
+        assert_eq!(r.read(&mut buf).unwrap(), 3);
+        assert_eq!(&buf[..3], b"abc");
+        assert_eq!(r.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf, b"\x1b[0m");
+    }
+
+    #[test]
+    fn test_split_reader_error() {
+        // None is an interrupted read
+        struct Interrupting(Vec<Option<&'static [u8]>>);
+
+        impl io::Read for Interrupting {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                match self.0.pop() {
+                    Some(Some(chunk)) => {
+                        buf[..chunk.len()].copy_from_slice(chunk);
+                        Ok(chunk.len())
+                    }
+                    Some(None) => Err(io::ErrorKind::Interrupted.into()),
+                    None => Ok(0),
+                }
+            }
+        }
+
+        let mut r = SplitReader::new(Interrupting(vec![Some(b"mX"), None, Some(b"ab\x1b[1")]));
+        let mut buf = [0u8; 64];
+        let mut out = Vec::new();
+        loop {
+            match r.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => out.extend_from_slice(&buf[..n]),
+                Err(e) => assert_eq!(e.kind(), io::ErrorKind::Interrupted),
+            }
+        }
+        assert_eq!(out, b"ab\x1b[1mX");
+    }
+
+    #[test]
+    fn test_incomplete_tail() {
+        assert_eq!(incomplete_tail(b"abc"), 0);
+        assert_eq!(incomplete_tail(b"a\x1b"), 1);
+        assert_eq!(incomplete_tail(b"a\x1b[12;"), 5);
+        assert_eq!(incomplete_tail(b"a\x1b[12;4H"), 0);
+        assert_eq!(incomplete_tail(b"\x1b]8;;x\x07"), 0);
+        assert_eq!(incomplete_tail(b"\x1b]8;;x\x1b"), 7);
+        assert_eq!(incomplete_tail(b"\x1b]8;;x\x1b["), 2);
+        assert_eq!(incomplete_tail(b"\x1b\x1b["), 2);
+    }
+}
@@ -0,0 +1,163 @@
+// Text measurement and layout aware of escape sequences
+
+use unicode_width::UnicodeWidthChar;
+
+/// Number of terminal columns s occupies.
+/// Escape sequences take no columns, east asian wide characters take two.
+pub fn display_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            width += char_width(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters and intermediates up to a final byte
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC, DCS, APC, PM, SOS: up to BEL or ST
+            Some(']') | Some('P') | Some('_') | Some('^') | Some('X') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    width
+}
+
+// control characters take no cell, the rest follow East Asian Width and combining marks
+fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+/// Lay out items in columns filled top to bottom, like ls.
+/// Uses as many columns as fit in width with two spaces between them. Returns the lines.
+pub fn columnate<S: AsRef<str>>(items: &[S], width: usize) -> Vec<String> {
+    const GAP: usize = 2;
+    if items.is_empty() {
+        return Vec::new();
+    }
+    let widths: Vec<usize> = items.iter().map(|s| display_width(s.as_ref())).collect();
+    let mut rows = 1;
+    let mut col_widths = Vec::new();
+    while rows < items.len() {
+        col_widths = widths
+            .chunks(rows)
+            .map(|c| c.iter().copied().max().unwrap_or(0))
+            .collect();
+        let total = col_widths.iter().sum::<usize>() + GAP * (col_widths.len() - 1);
+        if total <= width {
+            break;
+        }
+        rows += 1;
+    }
+    if rows == items.len() {
+        col_widths = vec![widths.iter().copied().max().unwrap_or(0)];
+    }
+    (0..rows)
+        .map(|row| {
+            let mut line = String::new();
+            let cells = (row..items.len()).step_by(rows);
+            for (col, i) in cells.enumerate() {
+                line.push_str(items[i].as_ref());
+                if i + rows < items.len() {
+                    line.push_str(&" ".repeat(col_widths[col] - widths[i] + GAP));
+                }
+            }
+            line
+        })
+        .collect()
+}
+
+/// Pad s with spaces on the left to width columns, aligning it right.
+/// s is returned unchanged if it is already as wide.
+pub fn pad_left(s: &str, width: usize) -> String {
+    let pad = width.saturating_sub(display_width(s));
+    format!("{}{}", " ".repeat(pad), s)
+}
+
+/// Pad s with spaces on the right to width columns, aligning it left.
+pub fn pad_right(s: &str, width: usize) -> String {
+    let pad = width.saturating_sub(display_width(s));
+    format!("{}{}", s, " ".repeat(pad))
+}
+
+/// Pad s with spaces on both sides to width columns.
+/// The extra space of an odd padding goes to the right.
+pub fn pad_center(s: &str, width: usize) -> String {
+    let pad = width.saturating_sub(display_width(s));
+    format!("{}{}{}", " ".repeat(pad / 2), s, " ".repeat(pad - pad / 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("\x1b[1;31mabc\x1b[0m"), 3);
+        assert_eq!(display_width("\x1b]8;;http://x\x1b\\link\x1b]8;;\x07"), 4);
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("e\u{301}"), 1);
+    }
+
+    #[test]
+    fn test_char_width() {
+        for c in "\0\t\n\x7f\u{85}".chars() {
+            assert_eq!(char_width(c), 0, "{:?}", c);
+        }
+        // combining marks and format characters
+        for c in "\u{301}\u{20dd}\u{0e31}\u{200b}\u{200d}\u{fe0f}\u{1ab0}".chars() {
+            assert_eq!(char_width(c), 0, "{:?}", c);
+        }
+        // CJK, hangul, kana, fullwidth forms and emoji
+        for c in
+            "日語\u{3400}\u{20000}\u{30000}가ひカＡ！￥\u{1f600}\u{1f680}\u{1fa70}\u{2614}\u{231a}"
+                .chars()
+        {
+            assert_eq!(char_width(c), 2, "{:?}", c);
+        }
+        // halfwidth forms and ambiguous width characters are narrow
+        for c in "aｱ\u{ffe8}é→─".chars() {
+            assert_eq!(char_width(c), 1, "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn test_columnate() {
+        let items = ["a", "bbb", "cc", "\x1b[1mdddd\x1b[0m", "e"];
+        assert_eq!(
+            columnate(&items, 80),
+            vec!["a  bbb  cc  \x1b[1mdddd\x1b[0m  e"]
+        );
+        assert_eq!(
+            columnate(&items, 12),
+            vec!["a    cc    e", "bbb  \x1b[1mdddd\x1b[0m"]
+        );
+        assert_eq!(
+            columnate(&items, 3),
+            vec!["a", "bbb", "cc", "\x1b[1mdddd\x1b[0m", "e"]
+        );
+        assert!(columnate::<&str>(&[], 80).is_empty());
+    }
+
+    #[test]
+    fn test_pad() {
+        let s = "\x1b[1mab\x1b[0m";
+        assert_eq!(pad_left(s, 5), format!("   {}", s));
+        assert_eq!(pad_right(s, 5), format!("{}   ", s));
+        assert_eq!(pad_center(s, 5), format!(" {}  ", s));
+        assert_eq!(pad_left("日本", 3), "日本");
+    }
+}
@@ -0,0 +1,146 @@
+// Writing with a timeout, so a stalled terminal can't freeze the application
+
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::time::{Duration, Instant};
+
+/// Writer which fails with `TimedOut` if the fd doesn't accept data within timeout,
+/// e.g. when the terminal is suspended or an SSH connection stalls.
+/// The fd is left in blocking mode, so stdin sharing the open file with stdout isn't affected.
+/// Instead it's polled before each write, and as a writable fd may have room for a single byte only,
+/// each write passes one byte to the fd. Wrap a `BufWriter` around it to batch small writes,
+/// the cost of a system call per byte remains.
+/// Bytes go to the fd directly, bypassing any buffer of inner, so flush inner before wrapping it.
+/// After a timeout, part of the data may have been written.
+pub struct TimeoutWriter<W: AsRawFd> {
+    inner: W,
+    timeout: Option<Duration>,
+}
+
+impl<W: AsRawFd> TimeoutWriter<W> {
+    /// Wrap inner, no timeout if timeout is None.
+    pub fn new(inner: W, timeout: Option<Duration>) -> TimeoutWriter<W> {
+        TimeoutWriter { inner, timeout }
+    }
+
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    // wait until the fd is writable or the timeout passes
+    fn wait(&self) -> io::Result<()> {
+        let deadline = self.timeout.map(|t| Instant::now() + t);
+        loop {
+            let ms = match deadline {
+                Some(d) => {
+                    let left = d.saturating_duration_since(Instant::now());
+                    // round up so that a sub-millisecond remainder still waits
+                    left.as_micros()
+                        .div_ceil(1000)
+                        .min(libc::c_int::MAX as u128) as libc::c_int
+                }
+                None => -1,
+            };
+            let mut fds = libc::pollfd {
+                fd: self.inner.as_raw_fd(),
+                events: libc::POLLOUT,
+                revents: 0,
+            };
+            match unsafe { libc::poll(&mut fds, 1, ms) } {
+                0 => return Err(io::Error::new(io::ErrorKind::TimedOut, "write timed out")),
+                n if n < 0 => {
+                    let e = io::Error::last_os_error();
+                    if e.kind() != io::ErrorKind::Interrupted {
+                        return Err(e);
+                    }
+                }
+                // POLLERR and POLLHUP are reported by the write itself
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+impl<W: AsRawFd> io::Write for TimeoutWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.wait()?;
+        let n = unsafe { libc::write(self.inner.as_raw_fd(), buf.as_ptr() as *const _, 1) };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(n as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::os::unix::io::FromRawFd;
+
+    #[test]
+    fn test_timeout_writer() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let (mut r, w) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+        let mut w = TimeoutWriter::new(w, Some(Duration::from_millis(10)));
+        w.write_all(b"abc").unwrap();
+        let mut buf = [0u8; 3];
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"abc");
+        // nobody reads, so the pipe fills up
+        let chunk = [0u8; 4096];
+        let err = loop {
+            if let Err(e) = w.write_all(&chunk) {
+                break e;
+            }
+        };
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        // the fd stays blocking
+        let flags = unsafe { libc::fcntl(w.get_ref().as_raw_fd(), libc::F_GETFL) };
+        assert_eq!(flags & libc::O_NONBLOCK, 0);
+    }
+
+    #[test]
+    fn test_timeout_writer_pty() {
+        let (mut master, mut slave) = (0, 0);
+        let ret = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                std::ptr::null(),
+            )
+        };
+        assert_eq!(ret, 0);
+        let (_master, slave) = unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave)) };
+        let mut w = TimeoutWriter::new(slave, Some(Duration::from_millis(200)));
+        // the master isn't read, chunks larger than the room left must time out, not block
+        let start = Instant::now();
+        let chunk = [b'x'; 3000];
+        let err = loop {
+            if let Err(e) = w.write_all(&chunk) {
+                break e;
+            }
+        };
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
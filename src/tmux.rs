@@ -0,0 +1,218 @@
+// tmux control mode (tmux -CC) client helpers
+// https://github.com/tmux/tmux/wiki/Control-Mode
+
+use std::io;
+use std::str::{self, FromStr};
+
+/// Message from tmux in control mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// Output of a command between %begin and %end (or %error).
+    Reply {
+        number: u64,
+        output: Vec<String>,
+        error: bool,
+    },
+    /// %output: data written by a pane.
+    Output { pane: usize, data: Vec<u8> },
+    /// %exit: the client is exiting.
+    Exit(Option<String>),
+    /// Other notifications, e.g. %window-add.
+    Notification { name: String, args: String },
+}
+
+/// Parser of control mode lines.
+#[derive(Debug, Default)]
+pub struct ControlParser {
+    block: Option<(u64, Vec<String>)>,
+}
+
+impl ControlParser {
+    pub fn new() -> ControlParser {
+        ControlParser::default()
+    }
+
+    /// Parse a line without the trailing newline.
+    /// Lines are bytes, as tmux passes pane output of 0x80 and above unescaped and
+    /// a UTF-8 character may be split across two %output lines.
+    /// Return None while a reply is being collected or for lines out of any block.
+    pub fn parse_line(&mut self, line: &[u8]) -> Option<Message> {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if let Some((number, output)) = self.block.as_mut() {
+            let (name, args) = split_notification(line);
+            let error = match name {
+                Some(b"%end") => false,
+                Some(b"%error") => true,
+                _ => {
+                    output.push(lossy(line));
+                    return None;
+                }
+            };
+            // %end and %error have the same number as %begin
+            if field(args, 1) != Some(*number) {
+                output.push(lossy(line));
+                return None;
+            }
+            let (number, output) = self.block.take()?;
+            return Some(Message::Reply {
+                number,
+                output,
+                error,
+            });
+        }
+        match split_notification(line) {
+            (Some(b"%begin"), args) => {
+                self.block = Some((field(args, 1)?, Vec::new()));
+                None
+            }
+            (Some(b"%output"), args) => {
+                let i = args.iter().position(|&b| b == b' ').unwrap_or(args.len());
+                let (pane, data) = args.split_at(i);
+                Some(Message::Output {
+                    pane: field(pane.strip_prefix(b"%")?, 0)?,
+                    data: unescape(data.strip_prefix(b" ").unwrap_or(data)),
+                })
+            }
+            (Some(b"%exit"), args) => Some(Message::Exit(if args.is_empty() {
+                None
+            } else {
+                Some(lossy(args))
+            })),
+            (Some(name), args) => Some(Message::Notification {
+                name: lossy(name),
+                args: lossy(args),
+            }),
+            (None, _) => None,
+        }
+    }
+}
+
+// "%name args" => (Some("%name"), "args")
+fn split_notification(line: &[u8]) -> (Option<&[u8]>, &[u8]) {
+    if !line.starts_with(b"%") {
+        return (None, line);
+    }
+    match line.iter().position(|&b| b == b' ') {
+        Some(i) => (Some(&line[..i]), &line[i + 1..]),
+        None => (Some(line), b""),
+    }
+}
+
+// n-th space separated number of args
+fn field<T: FromStr>(args: &[u8], n: usize) -> Option<T> {
+    let f = args.split(|&b| b == b' ').nth(n)?;
+    str::from_utf8(f).ok()?.parse().ok()
+}
+
+fn lossy(b: &[u8]) -> String {
+    String::from_utf8_lossy(b).into_owned()
+}
+
+// octal escapes (\ooo) of %output
+fn unescape(s: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        let oct = s
+            .get(i + 1..i + 4)
+            .filter(|o| o.iter().all(|b| (b'0'..=b'7').contains(b)));
+        match (s[i], oct) {
+            (b'\\', Some(o)) => {
+                out.push(o.iter().fold(0u8, |n, &b| n.wrapping_mul(8) + (b - b'0')));
+                i += 4;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Send a command to tmux.
+pub fn send_command<W: io::Write>(w: &mut W, command: &str) -> io::Result<()> {
+    if command.contains('\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "command must be a single line",
+        ));
+    }
+    writeln!(w, "{}", command)?;
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply() {
+        let mut p = ControlParser::new();
+        assert_eq!(p.parse_line(b"%begin 1363006971 2 1"), None);
+        assert_eq!(p.parse_line(b"0: ksh* (1 panes) [80x24]"), None);
+        assert_eq!(
+            p.parse_line(b"%end 1363006971 2 1"),
+            Some(Message::Reply {
+                number: 2,
+                output: vec!["0: ksh* (1 panes) [80x24]".to_string()],
+                error: false,
+            })
+        );
+        assert_eq!(p.parse_line(b"%begin 1363006971 3 1"), None);
+        assert_eq!(p.parse_line(b"parse error: unknown command: foo"), None);
+        assert_eq!(
+            p.parse_line(b"%error 1363006971 3 1"),
+            Some(Message::Reply {
+                number: 3,
+                output: vec!["parse error: unknown command: foo".to_string()],
+                error: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_notification() {
+        let mut p = ControlParser::new();
+        assert_eq!(
+            p.parse_line(b"%output %1 ls\\015\\012\\\\x"),
+            Some(Message::Output {
+                pane: 1,
+                data: b"ls\r\n\\\\x".to_vec(),
+            })
+        );
+        assert_eq!(
+            p.parse_line(b"%window-add @1"),
+            Some(Message::Notification {
+                name: "%window-add".to_string(),
+                args: "@1".to_string(),
+            })
+        );
+        assert_eq!(p.parse_line(b"%exit"), Some(Message::Exit(None)));
+        assert_eq!(
+            p.parse_line(b"%exit detached"),
+            Some(Message::Exit(Some("detached".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_parse_output_split_utf8() {
+        let mut p = ControlParser::new();
+        let mut data = Vec::new();
+        for line in [&b"%output %2 a\xe3\x81"[..], b"%output %2 \x82b"].iter() {
+            match p.parse_line(line) {
+                Some(Message::Output { pane: 2, data: d }) => data.extend(d),
+                m => panic!("unexpected {:?}", m),
+            }
+        }
+        assert_eq!(data, "aあb".as_bytes());
+    }
+
+    #[test]
+    fn test_send_command() {
+        let mut w = Vec::new();
+        send_command(&mut w, "list-windows").unwrap();
+        assert_eq!(w, b"list-windows\n");
+        assert!(send_command(&mut w, "a\nb").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+// Colorized formatting layer for tracing-subscriber
+
+use crate::level;
+use std::fmt::{self, Write as _};
+use std::io;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Layer writing events as "LEVEL span:span: target: message field=value" with the level colored.
+pub struct Layer<W: io::Write = io::Stderr> {
+    w: Mutex<W>,
+    color: bool,
+}
+
+impl Layer {
+    /// Write to stderr, color is enabled if stderr is a terminal.
+    pub fn new() -> Layer {
+        let color = unsafe { libc::isatty(2) } == 1;
+        Layer::with_writer(io::stderr()).color(color)
+    }
+}
+
+impl Default for Layer {
+    fn default() -> Layer {
+        Layer::new()
+    }
+}
+
+impl<W: io::Write> Layer<W> {
+    /// Write to w without color.
+    pub fn with_writer(w: W) -> Layer<W> {
+        Layer {
+            w: Mutex::new(w),
+            color: false,
+        }
+    }
+
+    pub fn color(mut self, color: bool) -> Layer<W> {
+        self.color = color;
+        self
+    }
+}
+
+impl<S, W> tracing_subscriber::Layer<S> for Layer<W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: io::Write + 'static,
+{
+    fn on_event(&self, event: &Event, ctx: Context<S>) {
+        let spans: Vec<&str> = ctx
+            .event_scope(event)
+            .map(|scope| scope.from_root().map(|span| span.name()).collect())
+            .unwrap_or_default();
+        if let Ok(mut w) = self.w.lock() {
+            let _ = format(&mut *w, event, &spans, self.color);
+        }
+    }
+}
+
+// message and "field=value" pairs of an event
+#[derive(Default)]
+struct Fields {
+    message: String,
+    rest: String,
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.rest, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.rest, " {}={}", field.name(), value);
+        }
+    }
+}
+
+// spans are from the root
+fn format<W: io::Write>(w: &mut W, event: &Event, spans: &[&str], color: bool) -> io::Result<()> {
+    let level = match *event.metadata().level() {
+        Level::ERROR => level::Level::Error,
+        Level::WARN => level::Level::Warn,
+        Level::INFO => level::Level::Info,
+        Level::DEBUG => level::Level::Debug,
+        Level::TRACE => level::Level::Trace,
+    };
+    let mut fields = Fields::default();
+    event.record(&mut fields);
+    level::write_line(
+        w,
+        level,
+        spans,
+        event.metadata().target(),
+        format_args!("{}{}", fields.message, fields.rest),
+        color,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn write_event(color: bool) -> Vec<u8> {
+        let w = Shared::default();
+        let layer = Layer::with_writer(w.clone()).color(color);
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("request").entered();
+            tracing::warn!(target: "app", path = "/", "disk {}% full", 90);
+        });
+        let out = w.0.lock().unwrap().clone();
+        out
+    }
+
+    #[test]
+    fn test_layer() {
+        assert_eq!(
+            write_event(true),
+            &b"\x1b[33mWARN \x1b[0m\x1b[1m request\x1b[22m: app: disk 90% full path=/\n"[..]
+        );
+        assert_eq!(
+            write_event(false),
+            &b"WARN  request: app: disk 90% full path=/\n"[..]
+        );
+    }
+}